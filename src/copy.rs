@@ -0,0 +1,173 @@
+use clap::Parser;
+use miette::{bail, IntoDiagnostic, Result};
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{json, Value};
+
+use crate::{format::write_json, Meilisearch};
+
+#[derive(Debug, Parser)]
+pub struct CopyIndex {
+    /// The index to copy.
+    src: String,
+    /// The index to create. It must not exist yet.
+    dst: String,
+    /// The address of the instance on which the index must be created. By default it's created on the same instance.
+    #[clap(long)]
+    to_addr: Option<String>,
+    /// The API key of the destination instance. By default the key provided by `-k` is used.
+    #[clap(long)]
+    to_key: Option<String>,
+    /// The API key of an embedder, in the format `embedder=key`. Meilisearch redacts the API keys
+    /// of the embedders so they can't be copied from the source index.
+    #[clap(long)]
+    embedder_api_key: Vec<String>,
+    /// Only copy the documents matching this filter
+    #[clap(long, aliases = &["filters"])]
+    filter: Option<String>,
+    /// Number of documents sent per request
+    #[clap(long, default_value = "10000")]
+    chunk_size: usize,
+}
+
+impl Meilisearch {
+    pub fn copy_index(&self, params: CopyIndex) -> Result<()> {
+        let src = Meilisearch {
+            index: params.src,
+            ..self.clone()
+        };
+        let dst = Meilisearch {
+            addr: params.to_addr.unwrap_or_else(|| self.addr.clone()),
+            key: params.to_key.or_else(|| self.key.clone()),
+            index: params.dst,
+            ..self.clone()
+        };
+
+        let (primary_key, mut settings) = src.fetch_index_definition()?;
+        let missing = restore_embedder_api_keys(&mut settings, &params.embedder_api_key)?;
+        if !missing.is_empty() {
+            log::warn!(
+                "The API keys of the embedders `{}` are redacted by Meilisearch, you'll need to set them again on the destination or use `--embedder-api-key`",
+                missing.join("`, `")
+            );
+        }
+        dst.create_index_from(&primary_key, &settings)?;
+
+        let mut tasks = Vec::new();
+        let mut chunk = Vec::with_capacity(params.chunk_size);
+        let mut sent = 0;
+        let mut send_chunk = |chunk: &mut Vec<Value>| -> Result<()> {
+            let response = dst
+                .post(format!("{}/indexes/{}/documents", dst.addr, dst.index))
+                .header(CONTENT_TYPE, "application/json")
+                .json(&chunk)
+                .send()
+                .into_diagnostic()?;
+            let task = dst.json_response(response)?;
+            tasks.extend(task["taskUid"].as_u64());
+            sent += chunk.len();
+            log::info!("Sent {sent} documents");
+            chunk.clear();
+            Ok(())
+        };
+        src.for_each_index_document(params.filter.clone(), true, |mut document| {
+            // The embeddings are copied as is instead of being generated again by the destination.
+            if let Some(Value::Object(vectors)) = document.get_mut("_vectors") {
+                for embedding in vectors.values_mut() {
+                    if let Some(embedding) = embedding.as_object_mut() {
+                        embedding.insert("regenerate".to_string(), json!(false));
+                    }
+                }
+            }
+            chunk.push(Value::Object(document));
+            if chunk.len() >= params.chunk_size {
+                send_chunk(&mut chunk)?;
+            }
+            Ok(())
+        })?;
+        if !chunk.is_empty() {
+            send_chunk(&mut chunk)?;
+        }
+
+        if self.r#async {
+            return Ok(());
+        }
+        for task in tasks {
+            dst.wait_for_task_uid(task)?;
+        }
+
+        let src_count = src.count_documents(params.filter)?;
+        let dst_count = dst.count_documents(None)?;
+        write_json(json!({
+            "src": { "addr": src.addr, "index": src.index, "documents": src_count },
+            "dst": { "addr": dst.addr, "index": dst.index, "documents": dst_count },
+        }))?;
+        if src_count != dst_count {
+            bail!("The destination contains {dst_count} documents instead of {src_count}");
+        }
+        Ok(())
+    }
+
+    /// Retrieve the primary key and the settings of the index.
+    pub fn fetch_index_definition(&self) -> Result<(Value, Value)> {
+        let response = self
+            .get(format!("{}/indexes/{}", self.addr, self.index))
+            .send()
+            .into_diagnostic()?;
+        let primary_key = self.json_response(response)?["primaryKey"].take();
+        let response = self
+            .get(format!("{}/indexes/{}/settings", self.addr, self.index))
+            .send()
+            .into_diagnostic()?;
+        let settings = self.json_response(response)?;
+        Ok((primary_key, settings))
+    }
+
+    /// Create the index with the primary key and the settings, and wait until it's ready.
+    pub fn create_index_from(&self, primary_key: &Value, settings: &Value) -> Result<()> {
+        log::info!("Creating the index `{}`", self.index);
+        let response = self
+            .post(format!("{}/indexes", self.addr))
+            .json(&json!({ "uid": self.index, "primaryKey": primary_key }))
+            .send()
+            .into_diagnostic()?;
+        self.wait_for_task(response)?;
+        let response = self
+            .patch(format!("{}/indexes/{}/settings", self.addr, self.index))
+            .json(settings)
+            .send()
+            .into_diagnostic()?;
+        self.wait_for_task(response)?;
+        Ok(())
+    }
+}
+
+/// Meilisearch redacts the API keys of the embedders, so they're removed from the settings and
+/// replaced by the keys provided in the format `embedder=key`.
+/// Returns the embedders whose API key was removed and not provided.
+pub fn restore_embedder_api_keys(settings: &mut Value, keys: &[String]) -> Result<Vec<String>> {
+    let Some(embedders) = settings["embedders"].as_object_mut() else {
+        return Ok(Vec::new());
+    };
+    let mut missing: Vec<String> = embedders
+        .iter_mut()
+        .filter_map(|(name, embedder)| {
+            let embedder = embedder.as_object_mut()?;
+            embedder.remove("apiKey")?;
+            Some(name.clone())
+        })
+        .collect();
+    for key in keys {
+        let Some((name, key)) = key.split_once('=') else {
+            bail!(
+                help = "Use `--embedder-api-key default=sk-...`",
+                "Invalid embedder API key `{key}`"
+            );
+        };
+        let Some(embedder) = embedders.get_mut(name.trim()) else {
+            bail!("The index has no embedder named `{}`", name.trim());
+        };
+        embedder["apiKey"] = json!(key);
+        missing.retain(|missing| missing != name.trim());
+    }
+    Ok(missing)
+}
@@ -1,17 +1,28 @@
-use crate::Meilisearch;
+use crate::{
+    editor,
+    format::write_json,
+    meilisearch::confirm,
+    profile::ProfileDocuments,
+    sync::SyncDocuments,
+    validate::{check_document_id, check_geo, check_vectors, get_field, Format},
+    Meilisearch,
+};
 use clap::Parser;
-use miette::{bail, IntoDiagnostic, Result};
-use reqwest::header::CONTENT_TYPE;
+use miette::{bail, miette, Context, IntoDiagnostic, Result};
+use reqwest::{blocking::RequestBuilder, header::CONTENT_TYPE};
 use serde::Serialize;
-use serde_json::json;
+use serde_json::{json, Map, Value};
 use std::{
     fs::File,
     io::{stdin, Read},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 pub type DocId = String;
 
+/// Number of documents retrieved per request while exporting an index.
+const EXPORT_PAGE_SIZE: usize = 1000;
+
 #[derive(Debug, Parser)]
 pub enum DocumentsCommand {
     /// Get one document. If no argument are specified it returns all documents.
@@ -42,9 +53,32 @@ pub enum DocumentsCommand {
     #[clap(aliases = &["u"])]
     Update(AddOrUpdate),
     /// Update documents with function
-    /// The payload must be sent through stdin
+    /// The function can be specified with `--function-file` or the whole payload can be sent through stdin
     #[clap(aliases = &["e"])]
-    Edit,
+    Edit(EditDocuments),
+    /// Edit a single document in your `$EDITOR`.
+    /// By default the document is replaced with the `post` verb, use `--partial` to update it with the `put` verb instead.
+    #[clap(aliases = &["eo", "edit_one", "editOne"])]
+    EditOne {
+        /// The id of the document you want to edit
+        id: DocId,
+        /// Update the document instead of replacing it. The fields you removed won't be deleted.
+        #[clap(long)]
+        partial: bool,
+    },
+    /// Validate documents locally without sending them.
+    /// Checks the primary key, the document ids and the shape of the `_geo` and `_vectors` fields.
+    #[clap(aliases = &["v", "check"])]
+    Validate(LocalDocuments),
+    /// Compare the documents of the index with a local file.
+    /// Reports the ids of the documents that were added, changed or removed in the file.
+    Diff(LocalDocuments),
+    /// Make the index mirror a local file.
+    /// Only the documents added or changed in the file are sent, and the documents missing from the file are deleted.
+    Sync(SyncDocuments),
+    /// Profile the fields of the documents and suggest settings.
+    /// Reports the type distribution, the null and missing rate, the cardinality, the min and max of the numbers and the length of the arrays.
+    Profile(ProfileDocuments),
     /// Delete documents. If no argument are specified all documents are deleted.
     #[clap(aliases = &["d", "rm", "remove"])]
     Delete {
@@ -54,25 +88,116 @@ pub enum DocumentsCommand {
         /// The filter used to delete the documents
         #[clap(long)]
         filter: Option<String>,
+        /// Don't ask for a confirmation before deleting the documents
+        #[clap(short, long)]
+        yes: bool,
+    },
+    /// Count the documents. If a filter is specified only the documents matching it are counted.
+    #[clap(aliases = &["c"])]
+    Count {
+        /// Refine results based on attributes in the `filterableAttributes` list
+        #[clap(long, aliases = &["filters"])]
+        filter: Option<String>,
+    },
+    /// Return a few documents, optionally matching a filter.
+    #[clap(aliases = &["s"])]
+    Sample {
+        #[clap(flatten)]
+        base_params: GetDocumentBaseParameter,
+        /// Refine results based on attributes in the `filterableAttributes` list
+        #[clap(long, aliases = &["filters"])]
+        filter: Option<String>,
+        /// Number of documents to return
+        #[clap(short, default_value = "5")]
+        n: usize,
+        /// Pick the documents at random offsets instead of returning the first ones
+        #[clap(long)]
+        random: bool,
     },
 }
 
-#[derive(Debug, Parser)]
+#[derive(Debug, Default, Parser)]
 pub struct AddOrUpdate {
     /// Set the content-type of your file. It should be either `application/json`, `application/x-ndjson`, `text/csv`.
     #[clap(short)]
-    content_type: Option<String>,
+    pub content_type: Option<String>,
     /// The primary key
     #[clap(short, long, aliases = &["primary-key", "primary_key", "primaryKey", "pk"])]
-    primary: Option<String>,
+    pub primary: Option<String>,
     /// Configure the character separating CSV fields. Must be a string containing one ASCII character.
     #[clap(long)]
-    csv_delimiter: Option<String>,
+    pub csv_delimiter: Option<String>,
+    /// Validate the documents locally before sending them. See `mieli documents validate`.
+    #[clap(long)]
+    pub validate: bool,
     /// The file you want to send
-    file: Option<PathBuf>,
+    pub file: Option<PathBuf>,
 }
 
-#[derive(Default, PartialEq, Eq, Debug, Parser, Serialize)]
+#[derive(Debug, Parser)]
+pub struct EditDocuments {
+    /// The file containing the Rhai function to apply on the documents
+    #[clap(long, aliases = &["script", "script-file"], conflicts_with = "function")]
+    function_file: Option<PathBuf>,
+    /// The Rhai function to apply on the documents
+    #[clap(long)]
+    function: Option<String>,
+    /// Only edit the documents matching this filter
+    #[clap(long, aliases = &["filters"])]
+    filter: Option<String>,
+    /// A json file whose content will be accessible from the function as `context`
+    #[clap(long)]
+    context_file: Option<PathBuf>,
+    /// Only apply the function to N of the matching documents and print them once edited.
+    /// The primary key must be in the `filterableAttributes`.
+    #[clap(long)]
+    preview: Option<usize>,
+}
+
+#[derive(Debug, Parser)]
+pub struct LocalDocuments {
+    /// Set the content-type of your file. It should be either `application/json`, `application/x-ndjson`, `text/csv`.
+    #[clap(short)]
+    pub content_type: Option<String>,
+    /// The primary key. If not specified it's retrieved from the index or inferred from the first document.
+    #[clap(short, long, aliases = &["primary-key", "primary_key", "primaryKey", "pk"])]
+    pub primary: Option<String>,
+    /// Configure the character separating CSV fields. Must be a string containing one ASCII character.
+    #[clap(long)]
+    pub csv_delimiter: Option<String>,
+    /// The file containing your documents
+    pub file: PathBuf,
+}
+
+impl LocalDocuments {
+    pub fn format(&self) -> Result<Format> {
+        Format::from_content_type(content_type(self.content_type.as_deref(), Some(&self.file)))
+    }
+}
+
+/// Pick `n` distinct offsets in `0..total` in ascending order.
+fn random_offsets(total: usize, n: usize) -> Vec<usize> {
+    let mut offsets = fastrand::choose_multiple(0..total, n);
+    offsets.sort_unstable();
+    offsets
+}
+
+/// Use the content-type specified by the user or infer it from the file extension.
+pub fn content_type<'a>(content_type: Option<&'a str>, file: Option<&Path>) -> &'a str {
+    if let Some(content_type) = content_type {
+        return content_type;
+    }
+    match file
+        .and_then(|filepath| filepath.extension())
+        .and_then(|ext| ext.to_str())
+    {
+        Some("csv") => "text/csv",
+        Some("jsonl") | Some("ndjson") | Some("jsonlines") => "application/x-ndjson",
+        _ => "application/json",
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Eq, Debug, Parser, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetDocumentBaseParameter {
     #[clap(long, aliases = &["field"])]
@@ -125,20 +250,88 @@ impl DocumentsCommand {
             }
             DocumentsCommand::Add(params) => meili.index_documents(params, false),
             DocumentsCommand::Update(params) => meili.index_documents(params, true),
-            DocumentsCommand::Edit => meili.edit_documents(),
+            DocumentsCommand::Edit(params) => meili.edit_documents(params),
+            DocumentsCommand::EditOne { id, partial } => meili.edit_one_document(id, partial),
+            DocumentsCommand::Validate(params) => {
+                let format = params.format()?;
+                let content = std::fs::read_to_string(&params.file).into_diagnostic()?;
+                meili.validate_documents(
+                    &params.file.to_string_lossy(),
+                    content,
+                    format,
+                    params.csv_delimiter.as_deref(),
+                    params.primary,
+                )
+            }
+            DocumentsCommand::Diff(params) => meili.diff_documents(params),
+            DocumentsCommand::Sync(params) => meili.sync_documents(params),
             DocumentsCommand::Delete {
                 ids: None,
                 filter: None,
-            } => meili.delete_all(),
-            DocumentsCommand::Delete { ids: Some(ids), .. } => match ids.as_slice() {
-                [] => meili.delete_all(),
-                [id] => meili.delete_one(id.clone()),
-                ids => meili.delete_batch(ids),
-            },
+                yes,
+            } => {
+                let total = meili.number_of_documents(&meili.index)?;
+                let prompt = format!(
+                    "Delete all the {total} documents of the index `{}`?",
+                    meili.index
+                );
+                if !confirm(&prompt, yes)? {
+                    return Ok(());
+                }
+                meili.delete_all()
+            }
+            DocumentsCommand::Delete {
+                ids: Some(ids),
+                yes,
+                ..
+            } => {
+                if ids.is_empty() {
+                    bail!(
+                        help = "Run `mieli documents delete` without `--ids` to delete all the documents",
+                        "No document ids were provided"
+                    );
+                }
+                let total = meili.number_of_documents(&meili.index)?;
+                let prompt = format!(
+                    "Delete {} documents out of the {total} documents of the index `{}`?",
+                    ids.len(),
+                    meili.index
+                );
+                if !confirm(&prompt, yes)? {
+                    return Ok(());
+                }
+                match ids.as_slice() {
+                    [id] => meili.delete_one(id.clone()),
+                    ids => meili.delete_batch(ids),
+                }
+            }
             DocumentsCommand::Delete {
                 filter: Some(filter),
+                yes,
                 ..
-            } => meili.delete_documents_by_filter(filter),
+            } => {
+                let total = meili.number_of_documents(&meili.index)?;
+                let count = meili.count_documents(Some(filter.clone()))?;
+                let prompt = format!(
+                    "Delete the {count} documents matching `{filter}` out of the {total} documents of the index `{}`?",
+                    meili.index
+                );
+                if !confirm(&prompt, yes)? {
+                    return Ok(());
+                }
+                meili.delete_documents_by_filter(filter)
+            }
+            DocumentsCommand::Profile(params) => meili.profile_documents(params),
+            DocumentsCommand::Count { filter } => {
+                write_json(json!(meili.count_documents(filter)?))?;
+                Ok(())
+            }
+            DocumentsCommand::Sample {
+                base_params,
+                filter,
+                n,
+                random,
+            } => meili.sample_documents(base_params, filter, n, random),
         }
     }
 }
@@ -164,6 +357,19 @@ impl Meilisearch {
         extra_params: GetDocumentExtraParameter,
         by_post: bool,
     ) -> Result<()> {
+        let response = self
+            .documents_request(base_params, extra_params, by_post)?
+            .send()
+            .into_diagnostic()?;
+        self.handle_response(response)
+    }
+
+    fn documents_request(
+        &self,
+        base_params: GetDocumentBaseParameter,
+        extra_params: GetDocumentExtraParameter,
+        by_post: bool,
+    ) -> Result<RequestBuilder> {
         #[derive(Serialize)]
         struct Params {
             #[serde(flatten)]
@@ -175,14 +381,12 @@ impl Meilisearch {
             base_params,
             extra_params,
         };
-        let response = if by_post {
+        Ok(if by_post {
             self.post(format!(
                 "{}/indexes/{}/documents/fetch",
                 self.addr, self.index,
             ))
             .json(&params)
-            .send()
-            .into_diagnostic()?
         } else {
             self.get(format!(
                 "{}/indexes/{}/documents{}",
@@ -190,40 +394,153 @@ impl Meilisearch {
                 self.index,
                 yaup::to_string(&params).into_diagnostic()?
             ))
-            .send()
-            .into_diagnostic()?
+        })
+    }
+
+    /// Call `f` on every document of the index matching the filter, retrieving them page by page.
+    pub fn for_each_index_document(
+        &self,
+        filter: Option<String>,
+        retrieve_vectors: bool,
+        mut f: impl FnMut(Map<String, Value>) -> Result<()>,
+    ) -> Result<()> {
+        let mut offset = 0;
+        loop {
+            let extra_params = GetDocumentExtraParameter {
+                offset: Some(offset),
+                limit: Some(EXPORT_PAGE_SIZE),
+                filter: filter.clone(),
+            };
+            // The `/documents/fetch` route expects a boolean, unlike the query parameter of `GET /documents`
+            let mut body = serde_json::to_value(extra_params).into_diagnostic()?;
+            if retrieve_vectors {
+                body["retrieveVectors"] = json!(true);
+            }
+            let response = self
+                .post(format!(
+                    "{}/indexes/{}/documents/fetch",
+                    self.addr, self.index
+                ))
+                .json(&body)
+                .send()
+                .into_diagnostic()?;
+            let mut page = self.json_response(response)?;
+            let Value::Array(documents) = page["results"].take() else {
+                bail!("Unexpected response from Meilisearch: {page}");
+            };
+            let total = page["total"].as_u64();
+            let len = documents.len();
+            for document in documents {
+                match document {
+                    Value::Object(document) => f(document)?,
+                    other => bail!("Unexpected document returned by Meilisearch: {other}"),
+                }
+            }
+            offset += len;
+            log::debug!("Exported {offset} documents");
+            if len == 0 || total.is_some_and(|total| offset as u64 >= total) {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn count_documents(&self, filter: Option<String>) -> Result<u64> {
+        let extra_params = GetDocumentExtraParameter {
+            limit: Some(0),
+            filter,
+            ..GetDocumentExtraParameter::default()
         };
-        self.handle_response(response)
+        let response = self
+            .documents_request(GetDocumentBaseParameter::default(), extra_params, true)?
+            .send()
+            .into_diagnostic()?;
+        let documents = self.json_response(response)?;
+        documents["total"]
+            .as_u64()
+            .ok_or_else(|| miette!("Unexpected response from Meilisearch: {documents}"))
+    }
+
+    fn sample_documents(
+        &self,
+        base_params: GetDocumentBaseParameter,
+        filter: Option<String>,
+        n: usize,
+        random: bool,
+    ) -> Result<()> {
+        if !random {
+            let extra_params = GetDocumentExtraParameter {
+                limit: Some(n),
+                filter,
+                ..GetDocumentExtraParameter::default()
+            };
+            let response = self
+                .documents_request(base_params, extra_params, true)?
+                .send()
+                .into_diagnostic()?;
+            let documents = self.json_response(response)?;
+            write_json(documents["results"].clone())?;
+            return Ok(());
+        }
+
+        let total = self.count_documents(filter.clone())? as usize;
+        let mut documents = Vec::with_capacity(n.min(total));
+        for offset in random_offsets(total, n) {
+            let extra_params = GetDocumentExtraParameter {
+                offset: Some(offset),
+                limit: Some(1),
+                filter: filter.clone(),
+            };
+            let response = self
+                .documents_request(base_params.clone(), extra_params, true)?
+                .send()
+                .into_diagnostic()?;
+            let mut page = self.json_response(response)?;
+            if let Some(document) = page["results"].get_mut(0) {
+                documents.push(document.take());
+            }
+        }
+        write_json(json!(documents))?;
+        Ok(())
     }
 
-    fn index_documents(&self, params: AddOrUpdate, reindex: bool) -> Result<()> {
+    pub fn index_documents(&self, params: AddOrUpdate, reindex: bool) -> Result<()> {
         let url = format!("{}/indexes/{}/documents", self.addr, self.index);
         let client = match reindex {
             false => self.post(url),
             true => self.put(url),
         };
-        let client = if let Some(content_type) = params.content_type {
-            client.header(CONTENT_TYPE, content_type)
-        } else {
-            match params
-                .file
-                .as_ref()
-                .and_then(|filepath| filepath.extension())
-                .and_then(|ext| ext.to_str())
-            {
-                Some("csv") => client.header(CONTENT_TYPE, "text/csv"),
-                Some("jsonl") | Some("ndjson") | Some("jsonlines") => {
-                    client.header(CONTENT_TYPE, "application/x-ndjson")
-                }
-                _ => client.header(CONTENT_TYPE, "application/json"),
-            }
-        };
-        let client = if let Some(primary_key) = params.primary {
+        let content_type = content_type(params.content_type.as_deref(), params.file.as_deref());
+        let client = client.header(CONTENT_TYPE, content_type);
+        let client = if let Some(ref primary_key) = params.primary {
             client.query(&[("primaryKey", primary_key)])
         } else {
             client
         };
 
+        if params.validate {
+            let (name, content) = match params.file {
+                Some(ref filepath) => (
+                    filepath.to_string_lossy().into_owned(),
+                    std::fs::read_to_string(filepath).into_diagnostic()?,
+                ),
+                None if atty::isnt(atty::Stream::Stdin) => {
+                    let mut buffer = String::new();
+                    stdin().read_to_string(&mut buffer).into_diagnostic()?;
+                    ("stdin".to_string(), buffer)
+                }
+                None => bail!("Did you forgot to pipe something in the command?"),
+            };
+            self.validate_documents(
+                &name,
+                content.clone(),
+                Format::from_content_type(content_type)?,
+                params.csv_delimiter.as_deref(),
+                params.primary,
+            )?;
+            let response = client.body(content).send().into_diagnostic()?;
+            return self.handle_response(response);
+        }
+
         let response = match params.file {
             Some(filepath) => {
                 let file = File::open(filepath).into_diagnostic()?;
@@ -259,7 +576,7 @@ impl Meilisearch {
         self.handle_response(response)
     }
 
-    fn delete_batch(&self, docids: &[DocId]) -> Result<()> {
+    pub fn delete_batch(&self, docids: &[DocId]) -> Result<()> {
         let response = self
             .post(format!(
                 "{}/indexes/{}/documents/delete-batch",
@@ -271,20 +588,162 @@ impl Meilisearch {
         self.handle_response(response)
     }
 
-    pub fn edit_documents(&self) -> std::result::Result<(), miette::Error> {
-        let value: serde_json::Value = if atty::isnt(atty::Stream::Stdin) {
-            serde_json::from_reader(stdin()).into_diagnostic()?
-        } else {
-            bail!("The payload must be sent through stdin with the edit document by filter route. See the documentation at https://www.meilisearch.com/docs/reference/api/documents#update-documents-with-function");
+    pub fn edit_documents(&self, params: EditDocuments) -> Result<()> {
+        let function = match (params.function, params.function_file) {
+            (Some(function), _) => Some(function),
+            (None, Some(path)) => Some(
+                std::fs::read_to_string(&path)
+                    .into_diagnostic()
+                    .with_context(|| format!("Could not read `{}`", path.display()))?,
+            ),
+            (None, None) => None,
+        };
+        let mut payload: Map<String, Value> = match function {
+            Some(function) => Map::from_iter([("function".to_string(), json!(function))]),
+            None if atty::isnt(atty::Stream::Stdin) => {
+                serde_json::from_reader(stdin()).into_diagnostic()?
+            }
+            None => bail!("The function must be specified with `--function-file` or the payload must be sent through stdin with the edit document by filter route. See the documentation at https://www.meilisearch.com/docs/reference/api/documents#update-documents-with-function"),
+        };
+        if let Some(filter) = params.filter {
+            payload.insert("filter".to_string(), json!(filter));
+        }
+        if let Some(path) = params.context_file {
+            let file = File::open(&path)
+                .into_diagnostic()
+                .with_context(|| format!("Could not open `{}`", path.display()))?;
+            let context: Value = serde_json::from_reader(file)
+                .into_diagnostic()
+                .context("Could not deserialize the context as json")?;
+            payload.insert("context".to_string(), context);
+        }
+
+        let preview_filter = match params.preview {
+            Some(preview) => {
+                let filter = self.preview_filter(payload.get("filter"), preview)?;
+                payload.insert("filter".to_string(), json!(filter));
+                Some((filter, preview))
+            }
+            None => None,
         };
 
         let response = self
             .post(format!("{}/indexes/{}/edit", self.addr, self.index))
             .header(CONTENT_TYPE, "application/json")
-            .json(&value)
+            .json(&payload)
+            .send()
+            .into_diagnostic()?;
+        self.handle_response(response)?;
+
+        if let (Some((filter, preview)), false) = (preview_filter, self.r#async) {
+            let response = self
+                .post(format!(
+                    "{}/indexes/{}/documents/fetch",
+                    self.addr, self.index
+                ))
+                .json(&json!({ "filter": filter, "limit": preview }))
+                .send()
+                .into_diagnostic()?;
+            let edited = self.json_response(response)?;
+            write_json(edited["results"].clone())?;
+        }
+        Ok(())
+    }
+
+    /// Select `limit` documents matching the filter and returns a filter matching only them.
+    fn preview_filter(&self, filter: Option<&Value>, limit: usize) -> Result<String> {
+        let Some(primary_key) = self.fetch_primary_key()? else {
+            bail!("The index `{}` has no primary key", self.index);
+        };
+        let mut body = json!({ "limit": limit, "fields": [primary_key] });
+        if let Some(filter) = filter {
+            body["filter"] = filter.clone();
+        }
+        let response = self
+            .post(format!(
+                "{}/indexes/{}/documents/fetch",
+                self.addr, self.index
+            ))
+            .json(&body)
             .send()
             .into_diagnostic()?;
+        let documents = self.json_response(response)?;
+        let ids: Vec<_> = documents["results"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|document| document.as_object())
+            .filter_map(|document| get_field(document, &primary_key))
+            .map(|id| match id {
+                Value::String(id) => json!(id).to_string(),
+                id => id.to_string(),
+            })
+            .collect();
+        if ids.is_empty() {
+            bail!("No document matches the filter");
+        }
+        log::info!("Previewing the edition on the documents {}", ids.join(", "));
+        Ok(format!("{primary_key} IN [{}]", ids.join(", ")))
+    }
 
+    fn edit_one_document(&self, docid: DocId, partial: bool) -> Result<()> {
+        let url = format!("{}/indexes/{}/documents", self.addr, self.index);
+        // Without the vectors, replacing the document would drop its embeddings.
+        let response = self
+            .get(format!("{url}/{docid}?retrieveVectors=true"))
+            .send()
+            .into_diagnostic()?;
+        let document = self.json_response(response)?;
+
+        // The edits are checked before closing the editor so they're not lost on a mistake.
+        let primary_key = self.fetch_primary_key()?;
+        let edited = editor::edit_json_until_checked(&document, |edited| {
+            let Value::Object(edited) = edited else {
+                bail!("The document must be a json object");
+            };
+            if let Some(primary_key) = &primary_key {
+                let before = document
+                    .as_object()
+                    .and_then(|document| get_field(document, primary_key));
+                let after = get_field(edited, primary_key);
+                if before != after {
+                    bail!(
+                        "The primary key `{primary_key}` of the document cannot be modified. It would create a new document instead of editing `{docid}`."
+                    );
+                }
+                if let Some(Err(message)) = after.map(check_document_id) {
+                    bail!("{message}");
+                }
+            }
+            if let Some(Err(message)) = edited.get("_geo").map(check_geo) {
+                bail!(
+                    help =
+                        r#"`_geo` must be `null` or an object like `{ "lat": 45.5, "lng": 3.2 }`"#,
+                    "{message}"
+                );
+            }
+            if let Some(Err(message)) = edited.get("_vectors").map(check_vectors) {
+                bail!(
+                    help = "`_vectors` must be an object mapping each embedder to its embeddings",
+                    "{message}"
+                );
+            }
+            Ok(())
+        })?;
+        if edited == document {
+            log::info!("The document was not modified");
+            return Ok(());
+        }
+
+        let client = match partial {
+            false => self.post(url),
+            true => self.put(url),
+        };
+        let response = client
+            .header(CONTENT_TYPE, "application/json")
+            .json(&[edited])
+            .send()
+            .into_diagnostic()?;
         self.handle_response(response)
     }
 
@@ -0,0 +1,85 @@
+use std::{io::Write, process::Command};
+
+use dialoguer::Confirm;
+use log::warn;
+use miette::{bail, Context, IntoDiagnostic, NamedSource, Report, Result};
+use serde_json::Value;
+
+use crate::validate::json_error;
+
+/// Write the json in a tempfile, open it in the `$EDITOR` of the user and returns the content of the file once it's closed.
+pub fn edit_json(value: &Value) -> Result<Vec<u8>> {
+    let content = serde_json::to_vec_pretty(value).into_diagnostic()?;
+    edit(&content)
+}
+
+/// Like `edit_json` but the editor is reopened with the error and the edits of the user until the content is a valid json.
+pub fn edit_json_until_valid(value: &Value) -> Result<Value> {
+    edit_json_until_checked(value, |_| Ok(()))
+}
+
+/// Like `edit_json_until_valid` but the editor is also reopened until the json passes the `check`.
+pub fn edit_json_until_checked(
+    value: &Value,
+    mut check: impl FnMut(&Value) -> Result<()>,
+) -> Result<Value> {
+    let mut content = serde_json::to_vec_pretty(value).into_diagnostic()?;
+    loop {
+        content = edit(&content)?;
+        let text = String::from_utf8_lossy(&content).into_owned();
+        let report = match serde_json::from_str(&text) {
+            Ok(value) => match check(&value) {
+                Ok(()) => return Ok(value),
+                Err(report) => report,
+            },
+            Err(error) => Report::new(json_error(&text, 0, error))
+                .with_source_code(NamedSource::new("edited.json", text)),
+        };
+        eprintln!("{report:?}");
+        if !Confirm::new()
+            .with_prompt("Reopen the editor to fix the json?")
+            .default(true)
+            .interact()
+            .into_diagnostic()?
+        {
+            bail!("The edits were discarded because they are not valid");
+        }
+    }
+}
+
+fn edit(content: &[u8]) -> Result<Vec<u8>> {
+    let mut tempfile = tempfile::Builder::new()
+        .suffix(".json")
+        .tempfile()
+        .into_diagnostic()?;
+    tempfile
+        .write_all(content)
+        .into_diagnostic()
+        .context("Could not write the json in a tempfile")?;
+    let path = tempfile.into_temp_path();
+
+    let editor = match std::env::var("EDITOR") {
+        Ok(editor) => editor,
+        Err(std::env::VarError::NotPresent) => "vi".to_string(),
+        Err(e) => {
+            warn!("Cannot read the `$EDITOR` env variable. `vi` will be used: {e}");
+            "vi".to_string()
+        }
+    };
+
+    let ret = Command::new(&editor)
+        .arg(path.as_os_str())
+        .stdin(std::process::Stdio::inherit())
+        .stdout(std::process::Stdio::inherit())
+        .stderr(std::process::Stdio::inherit())
+        .output();
+    if let Err(err) = ret {
+        warn!(
+            "Editor `{}` failed to edit the file at the path `{}`: {err}",
+            editor,
+            path.to_string_lossy()
+        );
+        Err(err).into_diagnostic()?;
+    }
+    std::fs::read(path).into_diagnostic()
+}
@@ -1,11 +1,10 @@
-use std::{io::stdin, process::Command};
+use std::io::stdin;
 
 use clap::Parser;
-use log::warn;
 use miette::{bail, Context, IntoDiagnostic, Result};
 use serde_json::{Map, Value};
 
-use crate::{format, Meilisearch};
+use crate::{editor, format, Meilisearch};
 
 #[derive(Debug, Parser)]
 pub enum Experimental {
@@ -65,39 +64,7 @@ impl Meilisearch {
             .send()
             .into_diagnostic()?;
         let features = format::write_response_full(response, self.verbose)?;
-        let mut tempfile = tempfile::Builder::new()
-            .suffix(".json")
-            .tempfile()
-            .into_diagnostic()?;
-        serde_json::to_writer_pretty(&mut tempfile, &features)
-            .into_diagnostic()
-            .context("Could not write the feature in a tempfile")?;
-        let path = tempfile.into_temp_path();
-
-        let editor = match std::env::var("EDITOR") {
-            Ok(editor) => editor,
-            Err(std::env::VarError::NotPresent) => "vi".to_string(),
-            Err(e) => {
-                warn!("Cannot read the `$EDITOR` env variable. `vi` will be used: {e}");
-                "vi".to_string()
-            }
-        };
-
-        let ret = Command::new(&editor)
-            .arg(path.as_os_str())
-            .stdin(std::process::Stdio::inherit())
-            .stdout(std::process::Stdio::inherit())
-            .stderr(std::process::Stdio::inherit())
-            .output();
-        if let Err(err) = ret {
-            warn!(
-                "Editor `{}` failed to edit the file at the path `{}`: {err}",
-                editor,
-                path.to_string_lossy()
-            );
-            Err(err).into_diagnostic()?;
-        }
-        let bytes = std::fs::read(path).into_diagnostic()?;
+        let bytes = editor::edit_json(&features)?;
         let value: Map<String, Value> = serde_json::from_slice(&bytes)
             .into_diagnostic()
             .context("Could not deserialize the payload as json")?;
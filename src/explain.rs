@@ -0,0 +1,220 @@
+use miette::{bail, Context, IntoDiagnostic, Result};
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{json, Map, Value};
+use termion::style;
+
+use crate::Meilisearch;
+
+/// Number of hits retrieved per request while looking for the documents to compare.
+const WHY_PAGE_SIZE: usize = 1000;
+
+impl Meilisearch {
+    /// Run the search and display every hit with the score of each ranking rule.
+    pub fn explain_search(&self, mut body: Map<String, Value>) -> Result<()> {
+        let primary_key = self.fetch_existing_primary_key()?;
+        body.insert("showRankingScore".to_string(), json!(true));
+        body.insert("showRankingScoreDetails".to_string(), json!(true));
+        let response = self.search_with_details(&body)?;
+        let hits = response["hits"].as_array().cloned().unwrap_or_default();
+        if hits.is_empty() {
+            log::info!("No document matches the search");
+            return Ok(());
+        }
+
+        let offset = response["offset"].as_u64().unwrap_or_else(|| {
+            let page = response["page"].as_u64().unwrap_or(1);
+            page.saturating_sub(1) * response["hitsPerPage"].as_u64().unwrap_or_default()
+        });
+        let colored = atty::is(atty::Stream::Stdout);
+        for (position, hit) in hits.iter().enumerate() {
+            if position != 0 {
+                println!();
+            }
+            let header = format!(
+                "{}. {}  {:.4}",
+                offset + position as u64 + 1,
+                document_id(&hit[&primary_key]),
+                hit["_rankingScore"].as_f64().unwrap_or_default()
+            );
+            if colored {
+                println!("{}{header}{}", style::Bold, style::Reset);
+            } else {
+                println!("{header}");
+            }
+            let rules = ranking_rules(&hit["_rankingScoreDetails"]);
+            let width = rules
+                .iter()
+                .map(|(name, _)| name.chars().count())
+                .max()
+                .unwrap_or_default();
+            for (name, details) in rules {
+                let line = format!(
+                    "   {name:width$}  {:6}  {}",
+                    score(details),
+                    describe(details)
+                );
+                println!("{}", line.trim_end());
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the first ranking rule that ranks the two documents differently.
+    pub fn why_search(&self, mut body: Map<String, Value>, a: &str, b: &str) -> Result<()> {
+        let primary_key = self.fetch_existing_primary_key()?;
+        body.insert("showRankingScoreDetails".to_string(), json!(true));
+        body.insert("attributesToRetrieve".to_string(), json!([primary_key]));
+        body.remove("page");
+        body.remove("hitsPerPage");
+
+        let mut found: [Option<(usize, Value)>; 2] = [None, None];
+        let mut offset = 0;
+        loop {
+            body.insert("offset".to_string(), json!(offset));
+            body.insert("limit".to_string(), json!(WHY_PAGE_SIZE));
+            let response = self.search_with_details(&body)?;
+            let hits = response["hits"].as_array().cloned().unwrap_or_default();
+            for (position, hit) in hits.iter().enumerate() {
+                let id = document_id(&hit[&primary_key]);
+                for (slot, expected) in found.iter_mut().zip([a, b]) {
+                    if slot.is_none() && id == expected {
+                        let details = hit["_rankingScoreDetails"].clone();
+                        *slot = Some((offset + position + 1, details));
+                    }
+                }
+            }
+            if found.iter().all(Option::is_some) || hits.len() < WHY_PAGE_SIZE {
+                break;
+            }
+            offset += WHY_PAGE_SIZE;
+        }
+
+        let [Some((rank_a, details_a)), Some((rank_b, details_b))] = found else {
+            let missing: Vec<&str> = found
+                .iter()
+                .zip([a, b])
+                .filter(|(slot, _)| slot.is_none())
+                .map(|(_, id)| id)
+                .collect();
+            bail!(
+                help = "Only the documents matching the search can be compared",
+                "The document `{}` is not in the results of the search",
+                missing.join("` and `")
+            );
+        };
+
+        println!("`{a}` is ranked {rank_a} and `{b}` is ranked {rank_b}.");
+        let rules_a = ranking_rules(&details_a);
+        let rules_b = ranking_rules(&details_b);
+        let mut tied = Vec::new();
+        for ((name, rule_a), (_, rule_b)) in rules_a.iter().zip(&rules_b) {
+            let (score_a, score_b) = (rule_a.get("score"), rule_b.get("score"));
+            let diverge = match (score_a, score_b) {
+                (Some(score_a), Some(score_b)) => score_a != score_b,
+                _ => rule_a.get("value") != rule_b.get("value"),
+            };
+            if !diverge {
+                tied.push(name.as_str());
+                continue;
+            }
+            if !tied.is_empty() {
+                println!("Both documents are tied on {}.", tied.join(", "));
+            }
+            println!("They diverge on `{name}`:");
+            let width = a.chars().count().max(b.chars().count());
+            for (id, rule) in [(a, rule_a), (b, rule_b)] {
+                let line = format!("   {id:width$}  {:6}  {}", score(rule), describe(rule));
+                println!("{}", line.trim_end());
+            }
+            return Ok(());
+        }
+        println!(
+            "Both documents are tied on every ranking rule, they're ordered by their internal id."
+        );
+        Ok(())
+    }
+
+    /// Like `fetch_primary_key` but fails if the index has no primary key.
+    pub fn fetch_existing_primary_key(&self) -> Result<String> {
+        match self.fetch_primary_key()? {
+            Some(primary_key) => Ok(primary_key),
+            None => bail!(
+                "The index `{}` doesn't exist or has no primary key",
+                self.index
+            ),
+        }
+    }
+
+    fn search_with_details(&self, body: &Map<String, Value>) -> Result<Value> {
+        let response = self
+            .post(format!("{}/indexes/{}/search", self.addr, self.index))
+            .header(CONTENT_TYPE, "application/json")
+            .json(body)
+            .send()
+            .into_diagnostic()?;
+        self.json_response(response)
+            .context("Could not retrieve the ranking score details")
+    }
+}
+
+/// The ranking rules of the details, in the order they were applied.
+fn ranking_rules(details: &Value) -> Vec<(String, &Value)> {
+    let mut rules: Vec<(String, &Value)> = details
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, details)| (name.clone(), details))
+        .collect();
+    rules.sort_by_key(|(_, details)| details["order"].as_u64().unwrap_or(u64::MAX));
+    rules
+}
+
+fn score(details: &Value) -> String {
+    details["score"]
+        .as_f64()
+        .map(|score| format!("{score:.4}"))
+        .unwrap_or_default()
+}
+
+/// A compact description of what was measured by a ranking rule.
+fn describe(details: &Value) -> String {
+    let number = |key: &str| details[key].as_f64().unwrap_or_default();
+    if details.get("matchingWords").is_some() {
+        format!(
+            "{}/{} words",
+            number("matchingWords"),
+            number("maxMatchingWords")
+        )
+    } else if details.get("typoCount").is_some() {
+        format!("{}/{} typos", number("typoCount"), number("maxTypoCount"))
+    } else if let Some(match_type) = details["matchType"].as_str() {
+        match match_type {
+            "exactMatch" => "exact match".to_string(),
+            "matchesStart" => "matches start".to_string(),
+            _ => "no exact match".to_string(),
+        }
+    } else if details.get("attributeRankingOrderScore").is_some() {
+        format!(
+            "attribute rank {:.2}, word distance {:.2}",
+            number("attributeRankingOrderScore"),
+            number("queryWordDistanceScore")
+        )
+    } else if let Some(similarity) = details["similarity"].as_f64() {
+        format!("similarity {similarity:.4}")
+    } else if let Some(value) = details.get("value") {
+        let mut description = format!("value {}", document_id(value));
+        if let Some(distance) = details["distance"].as_f64() {
+            description.push_str(&format!(", distance {distance}m"));
+        }
+        description
+    } else {
+        String::new()
+    }
+}
+
+fn document_id(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
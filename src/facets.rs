@@ -0,0 +1,154 @@
+use clap::Parser;
+use miette::{IntoDiagnostic, Result};
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{json, Map, Value};
+use termion::style;
+
+use crate::{
+    format::{write_bar_chart, write_json},
+    Meilisearch,
+};
+
+#[derive(Debug, Parser)]
+pub struct FacetSearch {
+    /// The facet to search in. It must be in the `filterableAttributes` list
+    facet: String,
+    /// The beginning of the facet values you're looking for. Returns the most frequent values if empty
+    query: Option<String>,
+    /// Only count the documents matching this filter
+    #[clap(long)]
+    filter: Option<String>,
+    /// Only count the documents matching this search query
+    #[clap(long = "q", aliases = &["search"])]
+    q: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct Facets {
+    /// The facets to display. By default all the facets of the `filterableAttributes` list are displayed
+    attributes: Vec<String>,
+    /// Only count the documents matching this filter
+    #[clap(long)]
+    filter: Option<String>,
+    /// Only count the documents matching this search query
+    #[clap(long = "q", aliases = &["search"])]
+    q: Option<String>,
+}
+
+impl Meilisearch {
+    pub fn facet_search(&self, params: FacetSearch) -> Result<()> {
+        let mut body = Map::new();
+        body.insert("facetName".to_string(), json!(params.facet));
+        if let Some(query) = params.query {
+            body.insert("facetQuery".to_string(), json!(query));
+        }
+        if let Some(filter) = params.filter {
+            body.insert("filter".to_string(), json!(filter));
+        }
+        if let Some(q) = params.q {
+            body.insert("q".to_string(), json!(q));
+        }
+        let response = self
+            .post(format!("{}/indexes/{}/facet-search", self.addr, self.index))
+            .header(CONTENT_TYPE, "application/json")
+            .json(&body)
+            .send()
+            .into_diagnostic()?;
+
+        if atty::isnt(atty::Stream::Stdout) {
+            return self.handle_response(response);
+        }
+        let response = self.json_response(response)?;
+        let hits = response["facetHits"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        if hits.is_empty() {
+            log::info!("No facet value found");
+            return Ok(());
+        }
+        let rows: Vec<(String, u64)> = hits
+            .iter()
+            .map(|hit| {
+                (
+                    value_to_string(&hit["value"]),
+                    hit["count"].as_u64().unwrap_or_default(),
+                )
+            })
+            .collect();
+        write_bar_chart(&rows);
+        Ok(())
+    }
+
+    /// Run a search without any hit to retrieve the facet distribution and stats.
+    pub fn facets(&self, params: Facets) -> Result<()> {
+        let facets = if params.attributes.is_empty() {
+            vec!["*".to_string()]
+        } else {
+            params.attributes
+        };
+        let mut body = Map::new();
+        body.insert("facets".to_string(), json!(facets));
+        body.insert("limit".to_string(), json!(0));
+        if let Some(filter) = params.filter {
+            body.insert("filter".to_string(), json!(filter));
+        }
+        if let Some(q) = params.q {
+            body.insert("q".to_string(), json!(q));
+        }
+        let response = self
+            .post(format!("{}/indexes/{}/search", self.addr, self.index))
+            .header(CONTENT_TYPE, "application/json")
+            .json(&body)
+            .send()
+            .into_diagnostic()?;
+        let response = self.json_response(response)?;
+
+        if atty::isnt(atty::Stream::Stdout) {
+            write_json(json!({
+                "facetDistribution": response["facetDistribution"],
+                "facetStats": response["facetStats"],
+            }))?;
+            return Ok(());
+        }
+        let distribution = response["facetDistribution"]
+            .as_object()
+            .cloned()
+            .unwrap_or_default();
+        if distribution.is_empty() {
+            log::info!("No facet value found");
+            return Ok(());
+        }
+        for (position, (facet, values)) in distribution.iter().enumerate() {
+            if position != 0 {
+                println!();
+            }
+            let mut rows: Vec<(String, u64)> = values
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(value, count)| (value.clone(), count.as_u64().unwrap_or_default()))
+                .collect();
+            // The most frequent values first, then in alphabetical order.
+            rows.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+            println!(
+                "{}{facet}{} ({} values)",
+                style::Bold,
+                style::Reset,
+                rows.len()
+            );
+            if let Some(stats) = response["facetStats"].get(facet) {
+                println!("min: {}, max: {}", stats["min"], stats["max"]);
+            }
+            write_bar_chart(&rows);
+        }
+        Ok(())
+    }
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
@@ -1,7 +1,7 @@
 use miette::{Context, IntoDiagnostic, Result};
 use reqwest::blocking::Response;
 use serde_json::Value;
-use termion::color;
+use termion::{color, style};
 
 pub fn write_response_headers(response: &Response, verbose: u8) -> Result<()> {
     let status = response.status();
@@ -64,6 +64,50 @@ pub fn write_json(response: Value) -> Result<Value> {
     Ok(response)
 }
 
+/// Print rows aligned in columns. The header is printed in bold if stdout is a terminal.
+pub fn write_table(header: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        line.join("  ").trim_end().to_string()
+    };
+
+    let header = line(header.to_vec());
+    if atty::is(atty::Stream::Stdout) {
+        println!("{}{header}{}", style::Bold, style::Reset);
+    } else {
+        println!("{header}");
+    }
+    for row in rows {
+        println!("{}", line(row.iter().map(String::as_str).collect()));
+    }
+}
+
+/// Format a number of bytes in a human readable way. Eg. `1.5 MiB`
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
 pub fn write_response_full(response: Response, verbose: u8) -> Result<Value> {
     write_response_headers(&response, verbose)?;
     let body = response
@@ -78,3 +122,41 @@ pub fn write_response_full(response: Response, verbose: u8) -> Result<Value> {
         .context(format!("While converting the body as json: {body:?}"))?;
     write_json(json)
 }
+
+/// Print a horizontal bar for every value, scaled to fit the terminal.
+/// The bars are colored if stdout is a terminal.
+pub fn write_bar_chart(rows: &[(String, u64)]) {
+    let Some(max) = rows.iter().map(|(_, count)| *count).max() else {
+        return;
+    };
+    let label_width = rows
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or_default();
+    let count_width = max.to_string().len();
+    let colored = atty::is(atty::Stream::Stdout);
+    let terminal_width = termion::terminal_size().map_or(80, |(width, _)| width as usize);
+    let bar_width = terminal_width
+        .saturating_sub(label_width + count_width + 4)
+        .max(10);
+
+    for (label, count) in rows {
+        let length = if max == 0 {
+            0
+        } else {
+            (*count as f64 / max as f64 * bar_width as f64).round() as usize
+        };
+        // Always show a sliver for the values that are not empty.
+        let bar = "█".repeat(length.max((*count > 0) as usize));
+        if colored {
+            println!(
+                "{label:label_width$}  {count:>count_width$} {}{bar}{}",
+                color::Fg(color::Cyan),
+                color::Fg(color::Reset)
+            );
+        } else {
+            println!("{label:label_width$}  {count:>count_width$} {bar}");
+        }
+    }
+}
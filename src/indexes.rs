@@ -1,9 +1,19 @@
-use clap::Parser;
-use miette::{IntoDiagnostic, Result};
+use clap::{Parser, ValueEnum};
+use miette::{bail, miette, IntoDiagnostic, Result};
+use reqwest::blocking::Response;
 use serde::Serialize;
-use serde_json::json;
+use serde_json::{json, Value};
 
-use crate::Meilisearch;
+use crate::{
+    copy::CopyIndex,
+    format::{human_size, write_json, write_table},
+    meilisearch::confirm,
+    reindex::Reindex,
+    Meilisearch,
+};
+
+/// Number of indexes retrieved per request when listing all the indexes.
+const INDEXES_PAGE_SIZE: usize = 1000;
 
 #[derive(Debug, Parser, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -16,6 +26,42 @@ pub struct ListIndexes {
     #[clap(long)]
     #[serde(skip_serializing_if = "Option::is_none")]
     limit: Option<u32>,
+    /// Merge the stats of each index (number of documents, size, number of fields).
+    /// The indexes are printed as a table if stdout is a terminal, and as a JSON array otherwise.
+    #[clap(long)]
+    #[serde(skip)]
+    stats: bool,
+    /// Only list the indexes whose uid matches this glob pattern. Eg. `--match 'logs-*'`
+    #[clap(long = "match", value_name = "PATTERN")]
+    #[serde(skip)]
+    pattern: Option<String>,
+    /// Sort the indexes. The numeric columns are sorted from the largest to the smallest.
+    #[clap(long, value_enum)]
+    #[serde(skip)]
+    sort: Option<SortIndexesBy>,
+    /// Reverse the order of the indexes
+    #[clap(long)]
+    #[serde(skip)]
+    reverse: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SortIndexesBy {
+    Uid,
+    Documents,
+    Size,
+    AvgDocumentSize,
+    Fields,
+    UpdatedAt,
+}
+
+impl SortIndexesBy {
+    fn requires_stats(self) -> bool {
+        matches!(
+            self,
+            Self::Documents | Self::Size | Self::AvgDocumentSize | Self::Fields
+        )
+    }
 }
 
 #[derive(Debug, Parser)]
@@ -23,11 +69,11 @@ pub enum IndexesCommand {
     /// List all indexes.
     #[clap(aliases = &["all"])]
     List(ListIndexes),
-    /// Get an index, by default use the index provided by `-i`.
+    /// Get indexes, by default use the index provided by `-i`.
     Get {
-        /// The index you want to retrieve.
+        /// The indexes you want to retrieve. Glob patterns like `logs-*` are matched against all the indexes.
         #[clap(aliases = &["idx", "uid", "index_uid", "indexUid"])]
-        index: Option<String>,
+        indexes: Vec<String>,
     },
     /// Create an index, by default use the index provided by `-i`.
     Create {
@@ -38,86 +84,414 @@ pub enum IndexesCommand {
         #[clap(short, long, aliases = &["primary-key", "primary_key", "primaryKey", "pk"])]
         primary: Option<String>,
     },
-    /// Update an index, by default use the index provided by `-i`.
+    /// Update indexes, by default use the index provided by `-i`.
     Update {
-        /// The index you want to update.
+        /// The indexes you want to update. Glob patterns like `logs-*` are matched against all the indexes.
         #[clap(aliases = &["idx", "uid", "index_uid", "indexUid"])]
-        index: Option<String>,
+        indexes: Vec<String>,
         /// Primary key
         #[clap(short, long, aliases = &["primary-key", "primary_key", "primaryKey", "pk"])]
         primary: Option<String>,
+        /// Don't ask for a confirmation when updating multiple indexes
+        #[clap(short, long)]
+        yes: bool,
     },
-    /// Delete an index, by default use the index provided by `-i`.
+    /// Delete indexes, by default use the index provided by `-i`. Asks for a confirmation unless `--yes` is set.
     #[clap(aliases = &["remove", "rm", "del", "d"])]
     Delete {
-        /// The index you want to delete.
+        /// The indexes you want to delete. Glob patterns like `logs-*` are matched against all the indexes.
         #[clap(aliases = &["idx", "uid", "index_uid", "indexUid"])]
-        index: Option<String>,
+        indexes: Vec<String>,
+        /// Don't ask for a confirmation before deleting the indexes
+        #[clap(short, long)]
+        yes: bool,
     },
+    /// Copy the settings and the documents of an index to a new index, possibly on another instance.
+    #[clap(aliases = &["cp"])]
+    Copy(CopyIndex),
+    /// Swap the documents, settings and task history of indexes.
+    Swap {
+        /// The pairs of indexes to swap, in the format `a:b`. All the swaps are done atomically.
+        #[clap(required = true)]
+        pairs: Vec<String>,
+    },
+    /// Reindex an index from a file without downtime.
+    /// The documents are sent to a temporary index with the same settings which is then swapped with the index.
+    Reindex(Reindex),
 }
 
 impl IndexesCommand {
     pub fn execute(self, meili: Meilisearch) -> Result<()> {
         match self {
             IndexesCommand::List(opt) => meili.get_all_indexes(opt),
-            IndexesCommand::Get { index } => meili.get_index(index),
+            IndexesCommand::Get { indexes } => meili.get_indexes(indexes),
             IndexesCommand::Create { index, primary } => meili.create_index(index, primary),
-            IndexesCommand::Update { index, primary } => meili.update_index(index, primary),
-            IndexesCommand::Delete { index } => meili.delete_index(index),
+            IndexesCommand::Update {
+                indexes,
+                primary,
+                yes,
+            } => meili.update_indexes(indexes, primary, yes),
+            IndexesCommand::Delete { indexes, yes } => meili.delete_indexes(indexes, yes),
+            IndexesCommand::Copy(params) => meili.copy_index(params),
+            IndexesCommand::Swap { pairs } => meili.swap_indexes(pairs),
+            IndexesCommand::Reindex(params) => meili.reindex(params),
         }
     }
 }
 
 impl Meilisearch {
     fn get_all_indexes(&self, opt: ListIndexes) -> Result<()> {
-        let url = format!("{}/indexes{}", self.addr, yaup::to_string(&opt).unwrap());
-        let response = self.get(url).send().into_diagnostic()?;
-        self.handle_response(response)
+        if !opt.stats && opt.pattern.is_none() && opt.sort.is_none() && !opt.reverse {
+            let url = format!("{}/indexes{}", self.addr, yaup::to_string(&opt).unwrap());
+            let response = self.get(url).send().into_diagnostic()?;
+            return self.handle_response(response);
+        }
+
+        let mut indexes = self.fetch_all_indexes()?;
+        if let Some(ref pattern) = opt.pattern {
+            indexes.retain(|index| glob_match(pattern, index["uid"].as_str().unwrap_or_default()));
+        }
+        let stats = opt.stats || opt.sort.is_some_and(SortIndexesBy::requires_stats);
+        if stats {
+            let response = self
+                .get(format!("{}/stats", self.addr))
+                .send()
+                .into_diagnostic()?;
+            let mut stats = self.json_response(response)?;
+            for index in &mut indexes {
+                let uid = index["uid"].as_str().unwrap_or_default();
+                let stats = stats["indexes"][uid].take();
+                index["numberOfDocuments"] = stats["numberOfDocuments"].clone();
+                index["isIndexing"] = stats["isIndexing"].clone();
+                index["rawDocumentDbSize"] = stats["rawDocumentDbSize"].clone();
+                index["avgDocumentSize"] = stats["avgDocumentSize"].clone();
+                index["numberOfFields"] = json!(stats["fieldDistribution"]
+                    .as_object()
+                    .map(|fields| fields.len()));
+            }
+        }
+
+        if let Some(sort) = opt.sort {
+            let number = |index: &Value, field: &str| index[field].as_u64().unwrap_or_default();
+            indexes.sort_by(|a, b| match sort {
+                SortIndexesBy::Uid => a["uid"].as_str().cmp(&b["uid"].as_str()),
+                SortIndexesBy::UpdatedAt => a["updatedAt"].as_str().cmp(&b["updatedAt"].as_str()),
+                SortIndexesBy::Documents => {
+                    number(b, "numberOfDocuments").cmp(&number(a, "numberOfDocuments"))
+                }
+                SortIndexesBy::Size => {
+                    number(b, "rawDocumentDbSize").cmp(&number(a, "rawDocumentDbSize"))
+                }
+                SortIndexesBy::AvgDocumentSize => {
+                    number(b, "avgDocumentSize").cmp(&number(a, "avgDocumentSize"))
+                }
+                SortIndexesBy::Fields => {
+                    number(b, "numberOfFields").cmp(&number(a, "numberOfFields"))
+                }
+            });
+        }
+        if opt.reverse {
+            indexes.reverse();
+        }
+        let indexes: Vec<Value> = indexes
+            .into_iter()
+            .skip(opt.offset.unwrap_or(0) as usize)
+            .take(opt.limit.map_or(usize::MAX, |limit| limit as usize))
+            .collect();
+
+        if atty::isnt(atty::Stream::Stdout) {
+            write_json(Value::Array(indexes))?;
+            return Ok(());
+        }
+        let text = |value: &Value| match value {
+            Value::Null => String::from("-"),
+            Value::String(s) => s.clone(),
+            value => value.to_string(),
+        };
+        let size = |value: &Value| value.as_u64().map_or_else(|| String::from("-"), human_size);
+        let mut header = vec!["uid", "primaryKey"];
+        if stats {
+            header.extend(["documents", "size", "avgDocumentSize", "fields", "indexing"]);
+        }
+        header.extend(["createdAt", "updatedAt"]);
+        let rows: Vec<Vec<String>> = indexes
+            .iter()
+            .map(|index| {
+                let mut row = vec![text(&index["uid"]), text(&index["primaryKey"])];
+                if stats {
+                    row.extend([
+                        text(&index["numberOfDocuments"]),
+                        size(&index["rawDocumentDbSize"]),
+                        size(&index["avgDocumentSize"]),
+                        text(&index["numberOfFields"]),
+                        text(&index["isIndexing"]),
+                    ]);
+                }
+                row.extend([text(&index["createdAt"]), text(&index["updatedAt"])]);
+                row
+            })
+            .collect();
+        write_table(&header, &rows);
+        Ok(())
     }
 
-    fn get_index(&self, index: Option<String>) -> Result<()> {
-        let index = index.unwrap_or_else(|| self.index.to_string());
-        let response = self
-            .get(format!("{}/indexes/{}", self.addr, index))
-            .send()
-            .into_diagnostic()?;
-        self.handle_response(response)
+    /// Retrieve all the indexes, going through all the pages of the listing.
+    pub fn fetch_all_indexes(&self) -> Result<Vec<Value>> {
+        let mut indexes = Vec::new();
+        loop {
+            let response = self
+                .get(format!(
+                    "{}/indexes?offset={}&limit={INDEXES_PAGE_SIZE}",
+                    self.addr,
+                    indexes.len()
+                ))
+                .send()
+                .into_diagnostic()?;
+            let mut page = self.json_response(response)?;
+            let Value::Array(results) = page["results"].take() else {
+                return Ok(indexes);
+            };
+            let done = results.len() < INDEXES_PAGE_SIZE;
+            indexes.extend(results);
+            if done {
+                return Ok(indexes);
+            }
+        }
+    }
+
+    fn get_indexes(&self, indexes: Vec<String>) -> Result<()> {
+        let Some(uids) = self.resolve_indexes(&indexes)? else {
+            let index = indexes.first().unwrap_or(&self.index);
+            let response = self
+                .get(format!("{}/indexes/{}", self.addr, index))
+                .send()
+                .into_diagnostic()?;
+            return self.handle_response(response);
+        };
+        let indexes: Vec<Value> = self
+            .fetch_all_indexes()?
+            .into_iter()
+            .filter(|index| uids.iter().any(|uid| index["uid"] == uid.as_str()))
+            .collect();
+        write_json(Value::Array(indexes))?;
+        Ok(())
     }
 
     fn create_index(&self, index: Option<String>, primary_key: Option<String>) -> Result<()> {
         let index = index.unwrap_or_else(|| self.index.to_string());
+        let response = self.create_index_request(&index, primary_key)?;
+        self.handle_response(response)
+    }
+
+    pub fn create_index_request(
+        &self,
+        index: &str,
+        primary_key: Option<String>,
+    ) -> Result<Response> {
         let mut body = json!({ "uid": index });
         if let Some(primary_key) = primary_key {
             body["primaryKey"] = json!(primary_key);
         }
-        let response = self
-            .post(format!("{}/indexes", self.addr))
+        self.post(format!("{}/indexes", self.addr))
             .json(&body)
             .send()
-            .into_diagnostic()?;
-        self.handle_response(response)
+            .into_diagnostic()
     }
 
-    fn update_index(&self, index: Option<String>, primary_key: Option<String>) -> Result<()> {
-        let index = index.unwrap_or_else(|| self.index.to_string());
+    fn update_indexes(
+        &self,
+        indexes: Vec<String>,
+        primary_key: Option<String>,
+        yes: bool,
+    ) -> Result<()> {
         let mut body = json!({});
         if let Some(primary_key) = primary_key {
             body["primaryKey"] = json!(primary_key);
         }
+        let Some(uids) = self.resolve_indexes(&indexes)? else {
+            let index = indexes.first().unwrap_or(&self.index);
+            let response = self.update_index(index, &body)?;
+            return self.handle_response(response);
+        };
+        if !self.confirm_bulk_operation("updated", &uids, yes)? {
+            return Ok(());
+        }
+        let tasks = uids
+            .iter()
+            .map(|uid| self.update_index(uid, &body))
+            .collect::<Result<Vec<_>>>()?;
+        self.wait_for_bulk_tasks(tasks)
+    }
+
+    pub fn update_index(&self, index: &str, body: &Value) -> Result<Response> {
         let url = format!("{}/indexes/{}", self.addr, index);
-        let mut response = self.patch(&url).json(&body).send().into_diagnostic()?;
+        let mut response = self.patch(&url).json(body).send().into_diagnostic()?;
         if response.status().as_u16() == 405 {
             response = self.post(url).send().into_diagnostic()?;
         }
-        self.handle_response(response)
+        Ok(response)
     }
 
-    fn delete_index(&self, index: Option<String>) -> Result<()> {
-        let index = index.unwrap_or_else(|| self.index.to_string());
+    fn delete_indexes(&self, indexes: Vec<String>, yes: bool) -> Result<()> {
+        let Some(uids) = self.resolve_indexes(&indexes)? else {
+            let index = indexes.first().unwrap_or(&self.index);
+            let count = self.number_of_documents(index)?;
+            let prompt = format!("Delete the index `{index}` and its {count} documents?");
+            if !confirm(&prompt, yes)? {
+                return Ok(());
+            }
+            let response = self
+                .delete(format!("{}/indexes/{}", self.addr, index))
+                .send()
+                .into_diagnostic()?;
+            return self.handle_response(response);
+        };
+        if !self.confirm_bulk_operation("deleted", &uids, yes)? {
+            return Ok(());
+        }
+        let tasks = uids
+            .iter()
+            .map(|uid| {
+                self.delete(format!("{}/indexes/{}", self.addr, uid))
+                    .send()
+                    .into_diagnostic()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.wait_for_bulk_tasks(tasks)
+    }
+
+    /// Resolve a list of uids and glob patterns against the listing of the indexes.
+    /// Returns `None` if there is at most one plain uid, in which case it's used as is.
+    fn resolve_indexes(&self, patterns: &[String]) -> Result<Option<Vec<String>>> {
+        let is_glob = |pattern: &String| pattern.contains(['*', '?']);
+        if patterns.len() <= 1 && !patterns.iter().any(is_glob) {
+            return Ok(None);
+        }
+        let existing: Vec<String> = self
+            .fetch_all_indexes()?
+            .into_iter()
+            .filter_map(|index| index["uid"].as_str().map(String::from))
+            .collect();
+        let mut uids: Vec<String> = Vec::new();
+        for pattern in patterns {
+            let matches: Vec<&String> = existing
+                .iter()
+                .filter(|uid| glob_match(pattern, uid))
+                .collect();
+            if matches.is_empty() {
+                if is_glob(pattern) {
+                    bail!("No index matches `{pattern}`");
+                }
+                bail!("Index `{pattern}` not found");
+            }
+            for uid in matches {
+                if !uids.contains(uid) {
+                    uids.push(uid.clone());
+                }
+            }
+        }
+        Ok(Some(uids))
+    }
+
+    /// Print the indexes affected by a bulk operation with their number of documents
+    /// and ask for a confirmation unless `yes` is set.
+    fn confirm_bulk_operation(&self, action: &str, uids: &[String], yes: bool) -> Result<bool> {
+        let response = self
+            .get(format!("{}/stats", self.addr))
+            .send()
+            .into_diagnostic()?;
+        let stats = self.json_response(response)?;
+        eprintln!("The following {} indexes will be {action}:", uids.len());
+        for uid in uids {
+            let count = stats["indexes"][uid]["numberOfDocuments"]
+                .as_u64()
+                .unwrap_or_default();
+            eprintln!("  - {uid} ({count} documents)");
+        }
+        confirm("Continue?", yes)
+    }
+
+    /// Wait for all the tasks enqueued by a bulk operation and print their final status.
+    /// The tasks are all enqueued before waiting so Meilisearch can process them together.
+    pub fn wait_for_bulk_tasks(&self, responses: Vec<Response>) -> Result<()> {
+        let tasks = responses
+            .into_iter()
+            .map(|response| self.json_response(response))
+            .collect::<Result<Vec<_>>>()?;
+        if self.r#async {
+            write_json(Value::Array(tasks))?;
+            return Ok(());
+        }
+        let mut failed = 0;
+        let mut summary = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            let Some(uid) = task["taskUid"].as_u64() else {
+                bail!("Meilisearch did not return a task: {task}");
+            };
+            let mut entry = json!({ "indexUid": task["indexUid"], "taskUid": uid });
+            match self.wait_for_task_uid(uid) {
+                Ok(task) => entry["status"] = task["status"].clone(),
+                Err(error) => {
+                    failed += 1;
+                    entry["status"] = json!("failed");
+                    entry["error"] = json!(error.to_string());
+                }
+            }
+            summary.push(entry);
+        }
+        let total = summary.len();
+        write_json(Value::Array(summary))?;
+        if failed > 0 {
+            bail!("{failed} of the {total} tasks failed");
+        }
+        Ok(())
+    }
+
+    fn swap_indexes(&self, pairs: Vec<String>) -> Result<()> {
+        let swaps = pairs
+            .iter()
+            .map(|pair| match pair.split_once(':') {
+                Some((a, b)) => Ok(json!({ "indexes": [a, b] })),
+                None => Err(miette!(
+                    "Invalid pair `{pair}`, it should be in the format `a:b`"
+                )),
+            })
+            .collect::<Result<Vec<_>>>()?;
         let response = self
-            .delete(format!("{}/indexes/{}", self.addr, index))
+            .post(format!("{}/swap-indexes", self.addr))
+            .json(&swaps)
             .send()
             .into_diagnostic()?;
         self.handle_response(response)
     }
 }
+
+/// Match a text against a glob pattern where `*` matches any sequence of characters and `?` matches one character.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and of the text it's matched against, to backtrack on mismatch.
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
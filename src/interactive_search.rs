@@ -1,10 +1,108 @@
-use inquire::Text;
-use miette::{IntoDiagnostic, Result};
+use miette::{bail, IntoDiagnostic, Result};
 use serde_json::{json, Map, Value};
-use std::io::stdout;
-use termion::{color, screen::IntoAlternateScreen};
+use std::io::{stdout, Write};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+use termion::{
+    clear, color, cursor, event::Key, input::TermRead, raw::IntoRawMode,
+    screen::IntoAlternateScreen, style,
+};
 
-use crate::Meilisearch;
+use crate::{meilisearch::meilisearch_error, similar::Similar, Meilisearch};
+
+const HELP: &str = "↑↓ move · enter open · ctrl-f filter · ctrl-s sort · ctrl-l limit · esc quit";
+const PAGER_HELP: &str = "↑↓ scroll · s similar documents · esc back";
+/// Time to wait after the last key before sending the search, to avoid a request per key when typing.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// The parameters of the search that can be edited from the interactive search.
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Filter,
+    Sort,
+    Limit,
+}
+
+enum View {
+    Hits,
+    Edit {
+        field: Field,
+        buffer: String,
+    },
+    /// Display some text that doesn't fit on the screen, like a document.
+    Pager {
+        title: String,
+        lines: Vec<String>,
+        scroll: usize,
+        /// The document displayed, used to list its similar documents.
+        document: Option<Value>,
+        /// The id of the request whose response will replace the lines.
+        loading: Option<u64>,
+    },
+}
+
+struct State {
+    query: String,
+    config: Map<String, Value>,
+    /// The last search response or the error to display instead.
+    response: std::result::Result<Value, String>,
+    /// The time between the moment the last search was sent and the moment its response was received.
+    latency: Option<Duration>,
+    /// The id of the last search sent, the responses of the previous ones are outdated.
+    last_search: u64,
+    /// Whether the response of the last search has not been received yet.
+    searching: bool,
+    /// The id of the last request sent for the pager.
+    last_request: u64,
+    selected: usize,
+    view: View,
+    /// An error to display in the status line until the next key is pressed.
+    message: Option<String>,
+}
+
+enum Event {
+    Key(Key),
+    /// The terminal can't be read anymore.
+    KeysClosed,
+    Response {
+        search: u64,
+        response: std::result::Result<Value, String>,
+        latency: Duration,
+    },
+    Document {
+        request: u64,
+        document: std::result::Result<Value, String>,
+    },
+    Similar {
+        request: u64,
+        hits: std::result::Result<Vec<Value>, String>,
+    },
+}
+
+enum Request {
+    Search {
+        id: u64,
+        query: String,
+        config: Map<String, Value>,
+    },
+    /// Fetch the whole document of a hit.
+    Document { id: u64, hit: Value },
+    Similar {
+        id: u64,
+        document: Value,
+        config: Map<String, Value>,
+    },
+}
+
+enum Action {
+    Continue,
+    Search,
+    /// Display the document of this hit.
+    Open(Value),
+    /// List the documents similar to this one.
+    Similar(Value),
+    Quit,
+}
 
 impl Meilisearch {
     pub fn run_interactive_search(
@@ -12,28 +110,292 @@ impl Meilisearch {
         base_search: String,
         base_search_config: Map<String, Value>,
     ) -> Result<()> {
-        let _screen = stdout().into_alternate_screen().into_diagnostic()?;
-        let available_lines = termion::terminal_size().expect("Unsupported terminal").1;
-
-        Text::new("Search:")
-            .with_suggester(&move |input| {
-                self.search_suggestor(&base_search_config, available_lines as usize, input)
-            })
-            .with_placeholder(&base_search)
-            .with_initial_value(&base_search)
-            .prompt()
+        let mut screen = stdout()
+            .into_raw_mode()
+            .into_diagnostic()?
+            .into_alternate_screen()
             .into_diagnostic()?;
+        let mut state = State {
+            query: base_search,
+            config: base_search_config,
+            response: Ok(Value::Null),
+            latency: None,
+            last_search: 0,
+            searching: true,
+            last_request: 0,
+            selected: 0,
+            view: View::Hits,
+            message: None,
+        };
+
+        // The keys and the responses are received from background threads
+        // so the interface is never blocked by the network.
+        let (events, receiver) = channel();
+        // The keys are read from the terminal since stdin may contain the piped search.
+        let tty = termion::get_tty().into_diagnostic()?;
+        let keys = events.clone();
+        std::thread::spawn(move || {
+            for key in tty.keys() {
+                let Ok(key) = key else { break };
+                if keys.send(Event::Key(key)).is_err() {
+                    return;
+                }
+            }
+            let _ = keys.send(Event::KeysClosed);
+        });
+        let (requests, receiver_requests) = channel();
+        let meili = self.clone();
+        std::thread::spawn(move || meili.run_requests(receiver_requests, events));
+
+        let send_search = |state: &State| {
+            requests
+                .send(Request::Search {
+                    id: state.last_search,
+                    query: state.query.clone(),
+                    config: state.config.clone(),
+                })
+                .into_diagnostic()
+        };
+        send_search(&state)?;
+        render(&mut screen, &state).into_diagnostic()?;
 
+        for event in receiver {
+            match event {
+                Event::KeysClosed => break,
+                Event::Key(key) => {
+                    state.message = None;
+                    match self.handle_key(&mut state, key) {
+                        Action::Quit => break,
+                        Action::Continue => (),
+                        Action::Search => {
+                            state.last_search += 1;
+                            state.searching = true;
+                            send_search(&state)?;
+                        }
+                        Action::Open(hit) => {
+                            // The hit is displayed until the whole document is fetched, since
+                            // some of its fields may not be retrieved by the search.
+                            state.last_request += 1;
+                            state.view = View::Pager {
+                                title: "Search hit (fetching the document…)".to_string(),
+                                lines: json_lines(&hit),
+                                scroll: 0,
+                                document: Some(hit.clone()),
+                                loading: Some(state.last_request),
+                            };
+                            requests
+                                .send(Request::Document {
+                                    id: state.last_request,
+                                    hit,
+                                })
+                                .into_diagnostic()?;
+                        }
+                        Action::Similar(document) => {
+                            state.last_request += 1;
+                            state.view = View::Pager {
+                                title: "Similar documents".to_string(),
+                                lines: vec!["searching…".to_string()],
+                                scroll: 0,
+                                document: None,
+                                loading: Some(state.last_request),
+                            };
+                            requests
+                                .send(Request::Similar {
+                                    id: state.last_request,
+                                    document,
+                                    config: state.config.clone(),
+                                })
+                                .into_diagnostic()?;
+                        }
+                    }
+                }
+                // The responses of outdated searches are dropped.
+                Event::Response { search, .. } if search != state.last_search => continue,
+                Event::Response {
+                    response, latency, ..
+                } => {
+                    state.response = response;
+                    state.latency = Some(latency);
+                    state.searching = false;
+                    state.selected = 0;
+                }
+                Event::Document { request, document } => {
+                    if let View::Pager {
+                        title,
+                        lines,
+                        document: displayed,
+                        loading,
+                        ..
+                    } = &mut state.view
+                    {
+                        if *loading == Some(request) {
+                            match document {
+                                Ok(document) => {
+                                    *title = "Document".to_string();
+                                    *lines = json_lines(&document);
+                                    *displayed = Some(document);
+                                }
+                                Err(e) => {
+                                    *title = "Search hit".to_string();
+                                    state.message = Some(e);
+                                }
+                            }
+                            *loading = None;
+                        }
+                    }
+                }
+                Event::Similar { request, hits } => {
+                    // The response is dropped if the pager waiting for it was closed.
+                    if let View::Pager { lines, loading, .. } = &mut state.view {
+                        if *loading == Some(request) {
+                            *lines = match hits {
+                                Ok(hits) if hits.is_empty() => {
+                                    vec!["No similar document found".to_string()]
+                                }
+                                Ok(hits) => hits.iter().flat_map(json_lines).collect(),
+                                Err(e) => vec![error_line(&e)],
+                            };
+                            *loading = None;
+                        }
+                    }
+                }
+            }
+            render(&mut screen, &state).into_diagnostic()?;
+        }
+        write!(screen, "{}", cursor::Show).into_diagnostic()?;
         Ok(())
     }
 
-    /// This could be faster by using smarter ways to check for matches, when dealing with larger datasets.
-    fn search_suggestor(
+    /// Send the requests of the interactive search. The searches are sent once no other search
+    /// has been received for the debounce delay. Stops when the interactive search is closed.
+    fn run_requests(&self, requests: Receiver<Request>, events: Sender<Event>) {
+        let mut pending_search = None;
+        loop {
+            let request = match pending_search {
+                None => requests.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                Some(_) => requests.recv_timeout(DEBOUNCE),
+            };
+            let event = match request {
+                Ok(search @ Request::Search { .. }) => {
+                    pending_search = Some(search);
+                    continue;
+                }
+                Ok(Request::Document { id, hit }) => Event::Document {
+                    request: id,
+                    document: self.fetch_hit_document(&hit).map_err(|e| e.to_string()),
+                },
+                Ok(Request::Similar {
+                    id,
+                    document,
+                    config,
+                }) => Event::Similar {
+                    request: id,
+                    hits: self
+                        .similar_hits(&config, &document)
+                        .map_err(|e| e.to_string()),
+                },
+                Err(RecvTimeoutError::Timeout) => {
+                    let Some(Request::Search { id, query, config }) = pending_search.take() else {
+                        continue;
+                    };
+                    let now = Instant::now();
+                    let response = self.interactive_search_request(&config, &query);
+                    Event::Response {
+                        search: id,
+                        response,
+                        latency: now.elapsed(),
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+            if events.send(event).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Update the state according to the key pressed.
+    fn handle_key(&self, state: &mut State, key: Key) -> Action {
+        let mut search = false;
+        let mut open = None;
+        let mut similar = None;
+        match &mut state.view {
+            View::Hits => match key {
+                Key::Esc | Key::Ctrl('c') | Key::Ctrl('d') => return Action::Quit,
+                Key::Up => state.selected = state.selected.saturating_sub(1),
+                Key::Down => {
+                    let hits = hits(&state.response).len();
+                    state.selected = (state.selected + 1).min(hits.saturating_sub(1));
+                }
+                Key::Char('\n') => {
+                    if let Some(hit) = hits(&state.response).get(state.selected) {
+                        let mut hit = hit.clone();
+                        if let Some(hit) = hit.as_object_mut() {
+                            hit.remove("_formatted");
+                        }
+                        open = Some(hit);
+                    }
+                }
+                Key::Ctrl('f') => state.view = edit(Field::Filter, &state.config),
+                Key::Ctrl('s') => state.view = edit(Field::Sort, &state.config),
+                Key::Ctrl('l') => state.view = edit(Field::Limit, &state.config),
+                Key::Char(c) => {
+                    state.query.push(c);
+                    search = true;
+                }
+                Key::Backspace => search = state.query.pop().is_some(),
+                _ => (),
+            },
+            View::Edit { field, buffer } => match key {
+                Key::Esc | Key::Ctrl('c') => state.view = View::Hits,
+                Key::Char('\n') => {
+                    match apply_edit(&mut state.config, *field, buffer) {
+                        Ok(()) => search = true,
+                        Err(e) => state.message = Some(e),
+                    }
+                    state.view = View::Hits;
+                }
+                Key::Char(c) => buffer.push(c),
+                Key::Backspace => {
+                    buffer.pop();
+                }
+                _ => (),
+            },
+            View::Pager {
+                lines,
+                scroll,
+                document,
+                ..
+            } => match key {
+                Key::Esc | Key::Char('q') | Key::Char('\n') => state.view = View::Hits,
+                Key::Up => *scroll = scroll.saturating_sub(1),
+                Key::Down => *scroll = (*scroll + 1).min(lines.len().saturating_sub(1)),
+                Key::PageUp => *scroll = scroll.saturating_sub(available_lines()),
+                Key::PageDown => {
+                    *scroll = (*scroll + available_lines()).min(lines.len().saturating_sub(1))
+                }
+                Key::Char('s') => similar = document.take(),
+                _ => (),
+            },
+        }
+
+        if let Some(hit) = open {
+            Action::Open(hit)
+        } else if let Some(document) = similar {
+            Action::Similar(document)
+        } else if search {
+            Action::Search
+        } else {
+            Action::Continue
+        }
+    }
+
+    /// Never fails, the errors are returned as a message to display.
+    fn interactive_search_request(
         &self,
         search_config: &Map<String, Value>,
-        available_lines: usize,
         input: &str,
-    ) -> Vec<String> {
+    ) -> std::result::Result<Value, String> {
         let mut search = search_config.clone();
         if search.get("attributesToHighlight").is_none() {
             search.insert("attributesToHighlight".to_string(), json!(["*"]));
@@ -44,42 +406,272 @@ impl Meilisearch {
             .post(format!("{}/indexes/{}/search", self.addr, self.index))
             .header("Content-Type", "application/json")
             .json(&search)
-            .send();
-        let response = match response {
-            Err(e) => {
-                return vec![
-                    "Cannot connect to Meilisearch:".to_string(),
-                    format!(
-                        "\t{}{}{}",
-                        color::Fg(color::Red),
-                        e,
-                        color::Fg(color::Reset)
-                    ),
-                ]
-            }
-            Ok(response) => response,
+            .send()
+            .map_err(|e| format!("Cannot connect to Meilisearch: {e}"))?;
+        let status = response.status();
+        let body = response
+            .json::<Value>()
+            .map_err(|e| format!("Invalid response from Meilisearch: {e}"))?;
+        if status.is_success() {
+            Ok(body)
+        } else {
+            Err(meilisearch_error(&body).to_string())
+        }
+    }
+
+    fn fetch_hit_document(&self, hit: &Value) -> Result<Value> {
+        let id = self.hit_id(hit)?;
+        let response = self
+            .get(format!(
+                "{}/indexes/{}/documents/{id}",
+                self.addr, self.index
+            ))
+            .send()
+            .into_diagnostic()?;
+        self.json_response(response)
+    }
+
+    fn similar_hits(&self, search_config: &Map<String, Value>, hit: &Value) -> Result<Vec<Value>> {
+        let id = self.hit_id(hit)?;
+        let embedder = match search_config
+            .get("hybrid")
+            .and_then(|hybrid| hybrid["embedder"].as_str())
+        {
+            Some(embedder) => embedder.to_string(),
+            None => self.default_embedder()?,
         };
-        if response.status().is_success() {
-            response.json::<Value>().unwrap()["hits"]
-                .as_array()
-                .unwrap()
-                .iter()
-                .map(|value| value.get("_formatted").unwrap())
-                .map(|value| colored_json::to_colored_json_auto(value).unwrap())
-                .map(|s| s.replace("<em>", &color::Fg(color::Red).to_string()))
-                .map(|s| s.replace("</em>", &color::Fg(color::Green).to_string()))
-                .scan(0, |line, value| {
-                    *line += value.lines().count() + 1;
-                    if *line > available_lines {
-                        None
-                    } else {
-                        Some(value)
-                    }
-                })
-                .fuse()
-                .collect()
+
+        let response = self.similar_request(Similar::new(id, embedder))?;
+        let response = self.json_response(response)?;
+        Ok(response["hits"].as_array().cloned().unwrap_or_default())
+    }
+
+    fn hit_id(&self, hit: &Value) -> Result<String> {
+        let primary_key = self.fetch_existing_primary_key()?;
+        match &hit[&primary_key] {
+            Value::String(id) => Ok(id.clone()),
+            Value::Number(id) => Ok(id.to_string()),
+            _ => bail!("Could not find the id of the selected document"),
+        }
+    }
+}
+
+fn edit(field: Field, config: &Map<String, Value>) -> View {
+    let buffer = match (field, config.get(field.key())) {
+        (_, None | Some(Value::Null)) => String::new(),
+        (Field::Sort, Some(Value::Array(sort))) => sort
+            .iter()
+            .map(|sort| sort.as_str().map_or_else(|| sort.to_string(), String::from))
+            .collect::<Vec<_>>()
+            .join(","),
+        (_, Some(Value::String(value))) => value.clone(),
+        (_, Some(value)) => value.to_string(),
+    };
+    View::Edit { field, buffer }
+}
+
+/// Update the search parameter with the edited value. An empty value removes the parameter.
+fn apply_edit(
+    config: &mut Map<String, Value>,
+    field: Field,
+    buffer: &str,
+) -> std::result::Result<(), String> {
+    let buffer = buffer.trim();
+    if buffer.is_empty() {
+        config.remove(field.key());
+        return Ok(());
+    }
+    let value = match field {
+        Field::Filter => json!(buffer),
+        Field::Sort => json!(buffer.split(',').map(str::trim).collect::<Vec<_>>()),
+        Field::Limit => match buffer.parse::<usize>() {
+            Ok(limit) => json!(limit),
+            Err(_) => {
+                return Err(format!(
+                    "The limit must be a positive integer, got `{buffer}`"
+                ))
+            }
+        },
+    };
+    config.insert(field.key().to_string(), value);
+    Ok(())
+}
+
+impl Field {
+    fn key(&self) -> &'static str {
+        match self {
+            Field::Filter => "filter",
+            Field::Sort => "sort",
+            Field::Limit => "limit",
+        }
+    }
+}
+
+fn hits(response: &std::result::Result<Value, String>) -> &[Value] {
+    match response {
+        Ok(response) => response["hits"].as_array().map_or(&[], Vec::as_slice),
+        Err(_) => &[],
+    }
+}
+
+/// The number of lines available to display the hits or a document.
+fn available_lines() -> usize {
+    let height = termion::terminal_size().map_or(24, |(_, height)| height as usize);
+    // The prompt, the status line and the help take three lines.
+    height.saturating_sub(3).max(1)
+}
+
+fn terminal_width() -> usize {
+    termion::terminal_size().map_or(80, |(width, _)| width as usize)
+}
+
+fn json_lines(value: &Value) -> Vec<String> {
+    let json = colored_json::to_colored_json_auto(value)
+        .unwrap_or_else(|_| serde_json::to_string_pretty(value).unwrap_or_default());
+    json.lines().map(String::from).collect()
+}
+
+fn error_line(error: &str) -> String {
+    format!(
+        "{}{error}{}",
+        color::Fg(color::Red),
+        color::Fg(color::Reset)
+    )
+}
+
+/// Render a hit on a single line with its highlighted terms, truncated to the width of the terminal.
+fn hit_line(hit: &Value, width: usize) -> String {
+    let formatted = hit.get("_formatted").unwrap_or(hit);
+    let text = match formatted.as_object() {
+        Some(fields) => fields
+            .iter()
+            .filter(|(field, _)| !field.starts_with('_'))
+            .map(|(field, value)| match value {
+                Value::String(s) => format!("{field}: {s}"),
+                value => format!("{field}: {value}"),
+            })
+            .collect::<Vec<_>>()
+            .join("  "),
+        None => formatted.to_string(),
+    };
+    highlight(&text.replace('\n', " "), width)
+}
+
+/// Replace the `<em>` tags with colors and truncate the text to `width` visible characters.
+fn highlight(text: &str, width: usize) -> String {
+    let mut line = String::new();
+    let mut visible = 0;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("<em>") {
+            line.push_str(&color::Fg(color::Red).to_string());
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("</em>") {
+            line.push_str(&color::Fg(color::Reset).to_string());
+            rest = after;
         } else {
-            vec![colored_json::to_colored_json_auto(&response.json::<Value>().unwrap()).unwrap()]
+            if visible == width {
+                break;
+            }
+            line.push(c);
+            visible += 1;
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    line.push_str(&color::Fg(color::Reset).to_string());
+    line
+}
+
+fn render(out: &mut impl Write, state: &State) -> std::io::Result<()> {
+    let width = terminal_width();
+    let available_lines = available_lines();
+    write!(out, "{}{}", clear::All, cursor::Goto(1, 1))?;
+
+    // The prompt
+    let prompt = match &state.view {
+        View::Edit { field, buffer } => format!("{}: {buffer}", field.key()),
+        View::Pager { title, .. } => title.clone(),
+        View::Hits => format!("Search: {}", state.query),
+    };
+    write!(out, "{}{prompt}{}", style::Bold, style::Reset)?;
+
+    // The status line
+    let status = match (&state.message, &state.response) {
+        (Some(message), _) | (None, Err(message)) => error_line(message),
+        (None, Ok(Value::Null)) => "searching…".to_string(),
+        (None, Ok(response)) => {
+            let total = response["estimatedTotalHits"]
+                .as_u64()
+                .or(response["totalHits"].as_u64())
+                .unwrap_or_default();
+            let mut status = format!(
+                "{total} hits in {}ms",
+                response["processingTimeMs"].as_u64().unwrap_or_default()
+            );
+            if let Some(latency) = state.latency {
+                status.push_str(&format!(" ({}ms round-trip)", latency.as_millis()));
+            }
+            if state.searching {
+                status.push_str(" · searching…");
+            }
+            for field in [Field::Filter, Field::Sort, Field::Limit] {
+                if let Some(value) = state.config.get(field.key()).filter(|v| !v.is_null()) {
+                    status.push_str(&format!(" · {}: {value}", field.key()));
+                }
+            }
+            format!(
+                "{}{status}{}",
+                color::Fg(color::LightBlack),
+                color::Fg(color::Reset)
+            )
+        }
+    };
+    write!(out, "{}{status}", cursor::Goto(1, 2))?;
+
+    // The content
+    let help = match &state.view {
+        View::Pager { lines, scroll, .. } => {
+            for (row, line) in lines.iter().skip(*scroll).take(available_lines).enumerate() {
+                write!(out, "{}{line}", cursor::Goto(1, row as u16 + 3))?;
+            }
+            PAGER_HELP
+        }
+        View::Hits | View::Edit { .. } => {
+            let hits = hits(&state.response);
+            // Scroll the hits to keep the selected one on the screen.
+            let first = (state.selected + 1).saturating_sub(available_lines);
+            for (row, hit) in hits.iter().enumerate().skip(first).take(available_lines) {
+                let line = hit_line(hit, width.saturating_sub(2));
+                write!(out, "{}", cursor::Goto(1, (row - first) as u16 + 3))?;
+                if row == state.selected {
+                    write!(out, "{}>{} {line}", style::Bold, style::Reset)?;
+                } else {
+                    write!(out, "  {line}")?;
+                }
+            }
+            match state.view {
+                View::Edit { .. } => "enter apply · empty to remove · esc cancel",
+                _ => HELP,
+            }
         }
+    };
+    write!(
+        out,
+        "{}{}{help}{}",
+        cursor::Goto(1, available_lines as u16 + 3),
+        color::Fg(color::LightBlack),
+        color::Fg(color::Reset)
+    )?;
+
+    // Put the cursor at the end of the prompt
+    match state.view {
+        View::Pager { .. } => write!(out, "{}", cursor::Hide)?,
+        _ => write!(
+            out,
+            "{}{}",
+            cursor::Goto(prompt.chars().count() as u16 + 1, 1),
+            cursor::Show
+        )?,
     }
+    out.flush()
 }
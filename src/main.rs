@@ -1,17 +1,33 @@
 #![doc = include_str!("../README.md")]
 
 mod batches;
+mod copy;
 mod documents;
+mod editor;
 mod experimental;
+mod explain;
+mod facets;
 mod format;
 mod indexes;
 mod inner;
 mod interactive_search;
 mod keys;
 mod log;
+mod manifest;
 mod meilisearch;
+mod multi_search;
 mod options;
+mod profile;
+mod reindex;
+mod search;
+mod settings;
+mod settings_diff;
+mod settings_import;
+mod settings_validate;
+mod similar;
+mod sync;
 mod tasks;
+mod validate;
 
 pub use crate::documents::DocumentsCommand;
 pub use crate::indexes::IndexesCommand;
@@ -43,16 +59,32 @@ fn main() -> Result<()> {
         Command::Inner(command) => command.execute(),
         Command::Documents(command) => command.execute(meili),
         Command::Da(params) => DocumentsCommand::Add(params).execute(meili),
-        Command::Dd { ids, filter } => DocumentsCommand::Delete { ids, filter }.execute(meili),
+        Command::Dd { ids, filter, yes } => {
+            DocumentsCommand::Delete { ids, filter, yes }.execute(meili)
+        }
         Command::Search {
             search_terms,
             interactive: false,
-        } => meili.search(search_terms.join(" ")),
+            params,
+        } => meili.search(search_terms.join(" "), params),
         Command::Search {
             search_terms,
             interactive: true,
-        } => meili.interactive_search(search_terms.join(" ")),
-        Command::Settings => meili.settings(),
+            params,
+        } => meili.interactive_search(search_terms.join(" "), params),
+        Command::FacetSearch(params) => meili.facet_search(params),
+        Command::Facets(params) => meili.facets(params),
+        Command::Similar(params) => meili.similar(params),
+        Command::MultiSearch(params) => meili.multi_search(params),
+        Command::Settings {
+            no_validate,
+            command: None,
+        } => meili.settings(!no_validate),
+        Command::Settings {
+            command: Some(command),
+            ..
+        } => command.execute(meili),
+        Command::Apply(params) => meili.apply_manifest(params),
         Command::Index(command) => command.execute(meili),
         Command::Dump => meili.create_dump(),
         Command::Snapshot => meili.create_snapshot(),
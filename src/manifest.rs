@@ -0,0 +1,278 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use clap::Parser;
+use miette::{Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use termion::color;
+
+use crate::{
+    meilisearch::confirm,
+    settings_diff::{diff_values, Difference},
+    Meilisearch,
+};
+
+#[derive(Debug, Parser)]
+pub struct ApplyManifest {
+    /// The manifest, in yaml or json, describing the indexes with their primary key and settings.
+    /// Eg.
+    /// ```yaml
+    /// indexes:
+    ///   movies:
+    ///     primaryKey: id
+    ///     settings:
+    ///       filterableAttributes: [genres, year]
+    /// ```
+    manifest: PathBuf,
+    /// Only print the changes that would be applied
+    #[clap(long, aliases = &["dry-run"])]
+    plan: bool,
+    /// Delete the indexes of the instance that are not in the manifest
+    #[clap(long)]
+    prune: bool,
+    /// Don't ask for a confirmation before applying the changes
+    #[clap(short, long)]
+    yes: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    indexes: BTreeMap<String, IndexDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct IndexDefinition {
+    primary_key: Option<String>,
+    #[serde(default)]
+    settings: Map<String, Value>,
+}
+
+#[derive(Debug)]
+enum Change {
+    Create {
+        uid: String,
+        primary_key: Option<String>,
+        settings: Map<String, Value>,
+    },
+    Update {
+        uid: String,
+        primary_key: Option<String>,
+        /// Only contains the settings that changed.
+        settings: Map<String, Value>,
+        differences: Vec<Difference>,
+    },
+    Delete {
+        uid: String,
+        documents: u64,
+    },
+}
+
+impl Meilisearch {
+    pub fn apply_manifest(&self, params: ApplyManifest) -> Result<()> {
+        let manifest = read_manifest(&params.manifest)?;
+        let changes = self.plan_changes(manifest, params.prune)?;
+        if changes.is_empty() {
+            log::info!("The instance is already up to date with the manifest");
+            return Ok(());
+        }
+
+        let colored = atty::is(atty::Stream::Stdout);
+        for change in &changes {
+            change.print(colored);
+        }
+        if params.plan || !confirm("Apply these changes?", params.yes)? {
+            return Ok(());
+        }
+
+        // All the tasks are enqueued first, Meilisearch processes them in order.
+        let mut tasks = Vec::new();
+        for change in changes {
+            match change {
+                Change::Create {
+                    uid,
+                    primary_key,
+                    settings,
+                } => {
+                    tasks.push(self.create_index_request(&uid, primary_key)?);
+                    if !settings.is_empty() {
+                        let body = serde_json::to_vec(&settings).into_diagnostic()?;
+                        tasks.push(self.update_settings(&uid, body)?);
+                    }
+                }
+                Change::Update {
+                    uid,
+                    primary_key,
+                    settings,
+                    ..
+                } => {
+                    if let Some(primary_key) = primary_key {
+                        tasks.push(self.update_index(&uid, &json!({ "primaryKey": primary_key }))?);
+                    }
+                    if !settings.is_empty() {
+                        let body = serde_json::to_vec(&settings).into_diagnostic()?;
+                        tasks.push(self.update_settings(&uid, body)?);
+                    }
+                }
+                Change::Delete { uid, .. } => tasks.push(
+                    self.delete(format!("{}/indexes/{}", self.addr, uid))
+                        .send()
+                        .into_diagnostic()?,
+                ),
+            }
+        }
+        self.wait_for_bulk_tasks(tasks)
+    }
+
+    /// Compare the manifest with the live instance.
+    fn plan_changes(&self, manifest: Manifest, prune: bool) -> Result<Vec<Change>> {
+        let live: BTreeMap<String, Value> = self
+            .fetch_all_indexes()?
+            .into_iter()
+            .filter_map(|index| Some((index["uid"].as_str()?.to_string(), index)))
+            .collect();
+
+        let mut changes = Vec::new();
+        for (uid, definition) in &manifest.indexes {
+            let Some(index) = live.get(uid) else {
+                changes.push(Change::Create {
+                    uid: uid.clone(),
+                    primary_key: definition.primary_key.clone(),
+                    settings: definition.settings.clone(),
+                });
+                continue;
+            };
+
+            let primary_key = definition
+                .primary_key
+                .clone()
+                .filter(|primary_key| index["primaryKey"] != primary_key.as_str());
+            let response = self
+                .get(format!("{}/indexes/{}/settings", self.addr, uid))
+                .send()
+                .into_diagnostic()?;
+            let live_settings = self.json_response(response)?;
+            // Only the settings specified in the manifest are compared.
+            let mut live_settings: Map<String, Value> = definition
+                .settings
+                .keys()
+                .map(|key| (key.clone(), live_settings[key].clone()))
+                .collect();
+            // Meilisearch redacts the api keys of the embedders, they can't be compared.
+            if let (Some(Value::Object(live)), Some(Value::Object(wanted))) = (
+                live_settings.get_mut("embedders"),
+                definition.settings.get("embedders"),
+            ) {
+                for (name, embedder) in live {
+                    let wanted = wanted.get(name).and_then(|embedder| embedder.get("apiKey"));
+                    if let (Some(live), Some(wanted)) = (embedder.get_mut("apiKey"), wanted) {
+                        *live = wanted.clone();
+                    }
+                }
+            }
+            let mut differences = Vec::new();
+            diff_values(
+                "",
+                &Value::Object(live_settings),
+                &Value::Object(definition.settings.clone()),
+                &mut differences,
+            );
+            let settings: Map<String, Value> = definition
+                .settings
+                .iter()
+                .filter(|(key, _)| {
+                    differences
+                        .iter()
+                        .any(|difference| difference.path().split('.').next() == Some(key.as_str()))
+                })
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+
+            if primary_key.is_some() || !settings.is_empty() {
+                changes.push(Change::Update {
+                    uid: uid.clone(),
+                    primary_key,
+                    settings,
+                    differences,
+                });
+            }
+        }
+
+        if prune {
+            for uid in live.keys() {
+                if !manifest.indexes.contains_key(uid) {
+                    changes.push(Change::Delete {
+                        uid: uid.clone(),
+                        documents: self.number_of_documents(uid)?,
+                    });
+                }
+            }
+        }
+        Ok(changes)
+    }
+}
+
+impl Change {
+    fn print(&self, colored: bool) {
+        let header = |sign: char, action: String, color: &dyn color::Color| {
+            if colored {
+                println!(
+                    "{}{sign} {action}{}",
+                    color::Fg(color),
+                    color::Fg(color::Reset)
+                );
+            } else {
+                println!("{sign} {action}");
+            }
+        };
+        match self {
+            Change::Create {
+                uid,
+                primary_key,
+                settings,
+            } => {
+                let primary_key = primary_key.as_deref().unwrap_or("inferred");
+                header(
+                    '+',
+                    format!("create `{uid}` with the primary key `{primary_key}`"),
+                    &color::Green,
+                );
+                for key in settings.keys() {
+                    println!("    {key}");
+                }
+            }
+            Change::Update {
+                uid,
+                primary_key,
+                differences,
+                ..
+            } => {
+                header('~', format!("update `{uid}`"), &color::Yellow);
+                if let Some(primary_key) = primary_key {
+                    println!("    primaryKey: {primary_key}");
+                }
+                for difference in differences {
+                    difference.print("    ", colored);
+                }
+            }
+            Change::Delete { uid, documents } => header(
+                '-',
+                format!("delete `{uid}` and its {documents} documents"),
+                &color::Red,
+            ),
+        }
+    }
+}
+
+fn read_manifest(path: &PathBuf) -> Result<Manifest> {
+    let content = std::fs::read_to_string(path)
+        .into_diagnostic()
+        .with_context(|| format!("Could not read `{}`", path.display()))?;
+    let manifest = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&content).into_diagnostic()
+    } else {
+        serde_norway::from_str(&content).into_diagnostic()
+    };
+    manifest.with_context(|| format!("Invalid manifest `{}`", path.display()))
+}
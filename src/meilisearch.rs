@@ -1,14 +1,17 @@
 use std::io::{stdin, Read};
 
 use crate::format::{write_json, write_response_full, write_response_headers};
+use crate::search::SearchParameters;
+use crate::settings_validate::report_settings_issues;
 use clap::Parser;
-use miette::{IntoDiagnostic, Result};
+use dialoguer::Confirm;
+use miette::{bail, IntoDiagnostic, MietteDiagnostic, Result};
 use reqwest::{
     blocking::{Client, RequestBuilder, Response},
     header::{CONTENT_TYPE, USER_AGENT},
     StatusCode,
 };
-use serde_json::{json, Map, Value};
+use serde_json::{json, Value};
 
 #[derive(Debug, Clone, Parser)]
 pub struct Meilisearch {
@@ -101,14 +104,19 @@ impl Meilisearch {
         req_builder.header(USER_AGENT, &self.user_agent)
     }
 
-    pub fn search(&self, search: String) -> Result<()> {
-        let mut value: Map<String, Value> = if atty::isnt(atty::Stream::Stdin) {
-            serde_json::from_reader(stdin()).into_diagnostic()?
-        } else {
-            Map::new()
-        };
-        if !search.is_empty() {
-            value.insert("q".to_string(), json!(search));
+    pub fn search(&self, search: String, params: SearchParameters) -> Result<()> {
+        let ratios = params.compare_ratios.clone();
+        let explain = params.explain;
+        let why = params.why.clone();
+        let value = params.into_search_body(search)?;
+        if !ratios.is_empty() {
+            return self.compare_semantic_ratios(value, &ratios);
+        }
+        if explain {
+            return self.explain_search(value);
+        }
+        if let [a, b] = why.as_slice() {
+            return self.why_search(value, a, b);
         }
         let response = self
             .post(format!("{}/indexes/{}/search", self.addr, self.index))
@@ -120,24 +128,16 @@ impl Meilisearch {
         self.handle_response(response)
     }
 
-    pub fn interactive_search(&self, search: String) -> Result<()> {
+    pub fn interactive_search(&self, search: String, params: SearchParameters) -> Result<()> {
         if atty::isnt(atty::Stream::Stdout) {
-            return self.search(search);
-        }
-
-        let mut value: Map<String, Value> = if atty::isnt(atty::Stream::Stdin) {
-            serde_json::from_reader(stdin()).into_diagnostic()?
-        } else {
-            Map::new()
-        };
-        if !search.is_empty() {
-            value.insert("q".to_string(), json!(search));
+            return self.search(search, params);
         }
 
+        let value = params.into_search_body(search.clone())?;
         self.run_interactive_search(search, value)
     }
 
-    pub fn settings(&self) -> Result<()> {
+    pub fn settings(&self, validate: bool) -> Result<()> {
         let response = if atty::is(atty::Stream::Stdin) {
             self.get(format!("{}/indexes/{}/settings", self.addr, self.index))
                 .send()
@@ -145,29 +145,36 @@ impl Meilisearch {
         } else {
             let mut buffer = Vec::new();
             stdin().read_to_end(&mut buffer).into_diagnostic()?;
-
-            let url = format!("{}/indexes/{}/settings", self.addr, self.index);
-            let mut response = self
-                .patch(&url)
-                .header(CONTENT_TYPE, "application/json")
-                .body(buffer.clone())
-                .send()
-                .into_diagnostic()?;
-
-            if response.status().as_u16() == 405 {
-                response = self
-                    .post(url)
-                    .header(CONTENT_TYPE, "application/json")
-                    .body(buffer)
-                    .send()
-                    .into_diagnostic()?;
+            if validate {
+                report_settings_issues("stdin", &String::from_utf8_lossy(&buffer), false)?;
             }
-            response
+            self.update_settings(&self.index, buffer)?
         };
 
         self.handle_response(response)
     }
 
+    /// Patch the settings of an index with a json body.
+    pub fn update_settings(&self, index: &str, body: Vec<u8>) -> Result<Response> {
+        let url = format!("{}/indexes/{}/settings", self.addr, index);
+        let mut response = self
+            .patch(&url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.clone())
+            .send()
+            .into_diagnostic()?;
+
+        if response.status().as_u16() == 405 {
+            response = self
+                .post(url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(body)
+                .send()
+                .into_diagnostic()?;
+        }
+        Ok(response)
+    }
+
     pub fn create_dump(&self) -> Result<()> {
         let response = self
             .post(format!("{}/dumps", self.addr))
@@ -208,6 +215,57 @@ impl Meilisearch {
         self.handle_response(response)
     }
 
+    /// Retrieve the number of documents of an index from its stats.
+    pub fn number_of_documents(&self, index: &str) -> Result<u64> {
+        let response = self
+            .get(format!("{}/indexes/{}/stats", self.addr, index))
+            .send()
+            .into_diagnostic()?;
+        let stats = self.json_response(response)?;
+        Ok(stats["numberOfDocuments"].as_u64().unwrap_or_default())
+    }
+
+    /// Retrieve the body of a response as json without printing it.
+    /// If the request failed, the error returned by Meilisearch is converted to a diagnostic.
+    pub fn json_response(&self, response: Response) -> Result<Value> {
+        write_response_headers(&response, self.verbose)?;
+        let status = response.status();
+        if status == StatusCode::NO_CONTENT {
+            return Ok(Value::Null);
+        }
+        let body: Value = response.json().into_diagnostic()?;
+        if status.is_success() {
+            return Ok(body);
+        }
+        Err(meilisearch_error(&body).into())
+    }
+
+    /// Wait until the task returned in the response is processed without printing anything.
+    /// Returns the task once it succeeded or an error if it failed.
+    pub fn wait_for_task(&self, response: Response) -> Result<Value> {
+        let task = self.json_response(response)?;
+        let Some(uid) = task["taskUid"].as_u64() else {
+            bail!("Meilisearch did not return a task: {task}");
+        };
+        self.wait_for_task_uid(uid)
+    }
+
+    pub fn wait_for_task_uid(&self, uid: u64) -> Result<Value> {
+        loop {
+            let response = self
+                .get(format!("{}/tasks/{}", self.addr, uid))
+                .send()
+                .into_diagnostic()?;
+            let task = self.json_response(response)?;
+            match task["status"].as_str() {
+                Some("succeeded") => return Ok(task),
+                Some("failed") => return Err(meilisearch_error(&task["error"]).into()),
+                Some("canceled") => bail!("The task {uid} has been canceled"),
+                _ => std::thread::sleep(std::time::Duration::from_millis(self.interval as u64)),
+            }
+        }
+    }
+
     pub fn handle_response(&self, response: Response) -> Result<()> {
         if response.status() == StatusCode::NO_CONTENT {
             return write_response_headers(&response, self.verbose);
@@ -283,3 +341,38 @@ impl Meilisearch {
         Ok(())
     }
 }
+
+/// Ask for a confirmation before a destructive operation, unless `yes` is set.
+/// Since nobody can answer the prompt when stdin is not a terminal, the operation is refused instead.
+pub fn confirm(prompt: &str, yes: bool) -> Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+    if atty::isnt(atty::Stream::Stdin) {
+        bail!(
+            help = "Use `--yes` to skip the confirmation",
+            "Refusing to continue without a confirmation because stdin is not a terminal"
+        );
+    }
+    Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()
+        .into_diagnostic()
+}
+
+/// Convert an error returned by Meilisearch to a diagnostic.
+pub fn meilisearch_error(error: &Value) -> MietteDiagnostic {
+    let mut diagnostic = MietteDiagnostic::new(
+        error["message"]
+            .as_str()
+            .map_or_else(|| error.to_string(), String::from),
+    );
+    if let Some(code) = error["code"].as_str() {
+        diagnostic = diagnostic.with_code(code);
+    }
+    if let Some(link) = error["link"].as_str() {
+        diagnostic = diagnostic.with_url(link);
+    }
+    diagnostic
+}
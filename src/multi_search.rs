@@ -0,0 +1,284 @@
+use std::io::{stdin, Read};
+
+use clap::Parser;
+use miette::{bail, Context, IntoDiagnostic, Result};
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{json, Map, Value};
+use termion::style;
+
+use crate::{format::write_json, Meilisearch};
+
+/// The parameters accepted in a `--query`, with the type of their value.
+const QUERY_PARAMETERS: &[(&str, ParameterKind)] = &[
+    ("indexUid", ParameterKind::String),
+    ("q", ParameterKind::String),
+    ("filter", ParameterKind::String),
+    ("sort", ParameterKind::List),
+    ("limit", ParameterKind::Number),
+    ("offset", ParameterKind::Number),
+    ("page", ParameterKind::Number),
+    ("hitsPerPage", ParameterKind::Number),
+    ("facets", ParameterKind::List),
+    ("attributesToRetrieve", ParameterKind::List),
+    ("attributesToHighlight", ParameterKind::List),
+    ("attributesToCrop", ParameterKind::List),
+    ("cropLength", ParameterKind::Number),
+    ("matchingStrategy", ParameterKind::String),
+    ("showRankingScore", ParameterKind::Bool),
+    ("showRankingScoreDetails", ParameterKind::Bool),
+    ("distinct", ParameterKind::String),
+    ("locales", ParameterKind::List),
+    ("retrieveVectors", ParameterKind::Bool),
+    ("weight", ParameterKind::Number),
+];
+
+#[derive(Debug, Clone, Copy)]
+enum ParameterKind {
+    String,
+    Number,
+    Bool,
+    /// A comma separated list
+    List,
+}
+
+#[derive(Debug, Parser)]
+pub struct MultiSearch {
+    /// A query to run, as a list of `key=value`. The value extends until the next known key so it can contain spaces.
+    /// Can be specified multiple times and is added to the queries piped in the command.
+    /// `index` is an alias for `indexUid` and `weight` sets the weight of the query in a federated search.
+    /// Eg. `--query 'index=movies q=batman filter=year > 2000 sort=year:desc'`
+    #[clap(long = "query", aliases = &["queries"])]
+    queries: Vec<String>,
+    /// Merge the results of all the queries in a single list of hits
+    #[clap(long)]
+    federated: bool,
+    /// Maximum number of merged hits returned. Implies `--federated`
+    #[clap(long)]
+    limit: Option<usize>,
+    /// Number of merged hits to skip. Implies `--federated`
+    #[clap(long)]
+    offset: Option<usize>,
+    /// Facets to return for an index, as `index=facet1,facet2`. Can be specified multiple times. Implies `--federated`
+    #[clap(long)]
+    facets_by_index: Vec<String>,
+    /// Merge the facets of all the indexes in a single facet distribution. Implies `--federated`
+    #[clap(long)]
+    merge_facets: bool,
+}
+
+impl Meilisearch {
+    pub fn multi_search(&self, params: MultiSearch) -> Result<()> {
+        let body = params.into_body()?;
+        let response = self
+            .post(format!("{}/multi-search", self.addr))
+            .header(CONTENT_TYPE, "application/json")
+            .json(&body)
+            .send()
+            .into_diagnostic()?;
+
+        if atty::isnt(atty::Stream::Stdout) {
+            return self.handle_response(response);
+        }
+        let response = self.json_response(response)?;
+        if response.get("results").is_some() {
+            write_grouped_results(&response)
+        } else {
+            write_federated_results(&body, &response)
+        }
+    }
+}
+
+impl MultiSearch {
+    fn into_body(self) -> Result<Map<String, Value>> {
+        let mut body: Map<String, Value> = Map::new();
+        if atty::isnt(atty::Stream::Stdin) {
+            let mut content = String::new();
+            stdin().read_to_string(&mut content).into_diagnostic()?;
+            if !content.trim().is_empty() {
+                body = serde_json::from_str(&content)
+                    .into_diagnostic()
+                    .context("Could not deserialize the piped multi-search as json")?;
+            }
+        }
+
+        let queries = body.entry("queries").or_insert_with(|| json!([]));
+        let Value::Array(queries) = queries else {
+            bail!("The `queries` of the piped multi-search must be an array");
+        };
+        for query in &self.queries {
+            queries.push(Value::Object(parse_query(query)?));
+        }
+        if queries.is_empty() {
+            bail!(
+                help =
+                    "Pipe a multi-search in the command or use `--query 'index=movies q=batman'`",
+                "No query to run"
+            );
+        }
+
+        let federate = self.federated
+            || self.limit.is_some()
+            || self.offset.is_some()
+            || !self.facets_by_index.is_empty()
+            || self.merge_facets;
+        if !federate {
+            return Ok(body);
+        }
+        let federation = body.entry("federation").or_insert_with(|| json!({}));
+        let Value::Object(federation) = federation else {
+            bail!("The `federation` of the piped multi-search must be an object");
+        };
+        if let Some(limit) = self.limit {
+            federation.insert("limit".to_string(), json!(limit));
+        }
+        if let Some(offset) = self.offset {
+            federation.insert("offset".to_string(), json!(offset));
+        }
+        for facets in &self.facets_by_index {
+            let Some((index, facets)) = facets.split_once('=') else {
+                bail!(
+                    help = "Use `--facets-by-index movies=genre,year`",
+                    "Invalid facets `{facets}`"
+                );
+            };
+            let facets: Vec<&str> = facets.split(',').map(str::trim).collect();
+            let facets_by_index = federation
+                .entry("facetsByIndex")
+                .or_insert_with(|| json!({}));
+            facets_by_index[index.trim()] = json!(facets);
+        }
+        if self.merge_facets {
+            federation.entry("mergeFacets").or_insert_with(|| json!({}));
+        }
+        Ok(body)
+    }
+}
+
+/// Parse a query in the format `index=movies q=batman filter=year > 2000`.
+fn parse_query(query: &str) -> Result<Map<String, Value>> {
+    let mut parameters: Vec<(&str, ParameterKind, String)> = Vec::new();
+    for word in query.split_whitespace() {
+        // The booleans can be specified without a value. Eg. `showRankingScore`
+        let (key, value) = word.split_once('=').unwrap_or((word, ""));
+        let name = match key {
+            "index" | "index-uid" => "indexUid",
+            key => key,
+        };
+        let parameter = QUERY_PARAMETERS
+            .iter()
+            .find(|(parameter, _)| parameter.eq_ignore_ascii_case(&name.replace('-', "")))
+            .filter(|(_, kind)| word.contains('=') || matches!(kind, ParameterKind::Bool))
+            .map(|(parameter, kind)| (*parameter, *kind, value.to_string()));
+        match (parameter, parameters.last_mut()) {
+            (Some(parameter), _) => parameters.push(parameter),
+            (None, Some((_, _, value))) => {
+                value.push(' ');
+                value.push_str(word);
+            }
+            (None, None) => bail!(
+                help = "A query must start with a parameter. Eg. `index=movies q=batman`",
+                "Invalid query `{query}`"
+            ),
+        }
+    }
+
+    let mut body = Map::new();
+    for (name, kind, value) in parameters {
+        let value = match kind {
+            ParameterKind::String => json!(value),
+            ParameterKind::List => json!(value.split(',').map(str::trim).collect::<Vec<_>>()),
+            ParameterKind::Number => match serde_json::from_str::<serde_json::Number>(&value) {
+                Ok(number) => Value::Number(number),
+                Err(_) => bail!("`{name}` must be a number in `{query}`"),
+            },
+            ParameterKind::Bool => match value.as_str() {
+                "" | "true" => json!(true),
+                "false" => json!(false),
+                _ => bail!("`{name}` must be `true` or `false` in `{query}`"),
+            },
+        };
+        if name == "weight" {
+            body.insert("federationOptions".to_string(), json!({ "weight": value }));
+        } else {
+            body.insert(name.to_string(), value);
+        }
+    }
+    if !body.contains_key("indexUid") {
+        bail!(
+            help = "Specify the index with `index=movies`",
+            "Missing index in the query `{query}`"
+        );
+    }
+    Ok(body)
+}
+
+/// Print the hits of every query under a header describing the query.
+fn write_grouped_results(response: &Value) -> Result<()> {
+    let results = response["results"].as_array().cloned().unwrap_or_default();
+    for (position, result) in results.iter().enumerate() {
+        if position != 0 {
+            println!();
+        }
+        let hits = result["hits"].as_array().map_or(0, Vec::len);
+        let total = result["estimatedTotalHits"]
+            .as_u64()
+            .or(result["totalHits"].as_u64())
+            .unwrap_or_default();
+        println!(
+            "{}{}: {} ({hits} of ~{total} hits in {}ms){}",
+            style::Bold,
+            result["indexUid"].as_str().unwrap_or_default(),
+            result["query"],
+            result["processingTimeMs"],
+            style::Reset,
+        );
+        write_json(result["hits"].clone())?;
+        if let Some(facets) = result.get("facetDistribution") {
+            println!("{}facets{}", style::Bold, style::Reset);
+            write_json(facets.clone())?;
+        }
+    }
+    Ok(())
+}
+
+/// Print the merged hits, each one prefixed by the index and the weight of the query it comes from.
+fn write_federated_results(body: &Map<String, Value>, response: &Value) -> Result<()> {
+    let hits = response["hits"].as_array().cloned().unwrap_or_default();
+    println!(
+        "{}{} of ~{} hits in {}ms{}",
+        style::Bold,
+        hits.len(),
+        response["estimatedTotalHits"]
+            .as_u64()
+            .or(response["totalHits"].as_u64())
+            .unwrap_or_default(),
+        response["processingTimeMs"],
+        style::Reset,
+    );
+    for mut hit in hits {
+        let federation = hit
+            .as_object_mut()
+            .and_then(|hit| hit.remove("_federation"))
+            .unwrap_or_default();
+        let position = federation["queriesPosition"].as_u64().unwrap_or_default() as usize;
+        let weight = body["queries"][position]["federationOptions"]["weight"]
+            .as_f64()
+            .unwrap_or(1.0);
+        let mut annotation = format!(
+            "{} (query {position}, weight {weight}",
+            federation["indexUid"].as_str().unwrap_or_default()
+        );
+        if let Some(score) = federation["weightedRankingScore"].as_f64() {
+            annotation.push_str(&format!(", score {score:.4}"));
+        }
+        println!("{}{annotation}){}", style::Bold, style::Reset);
+        write_json(hit)?;
+    }
+    for facets in ["facetsByIndex", "facetDistribution"] {
+        if let Some(value) = response.get(facets) {
+            println!("{}{facets}{}", style::Bold, style::Reset);
+            write_json(value.clone())?;
+        }
+    }
+    Ok(())
+}
@@ -4,9 +4,15 @@ use crate::{
     batches::BatchesCommand,
     documents::{AddOrUpdate, DocId},
     experimental::Experimental,
+    facets::{FacetSearch, Facets},
     inner::Inner,
     log::Log,
+    manifest::ApplyManifest,
     meilisearch::Meilisearch,
+    multi_search::MultiSearch,
+    search::SearchParameters,
+    settings::SettingsCommand,
+    similar::Similar,
     tasks::{TaskListParameters, TasksCommand},
     DocumentsCommand, IndexesCommand, Key,
 };
@@ -42,6 +48,9 @@ pub enum Command {
         /// The filter used to delete the documents
         #[clap(long)]
         filter: Option<String>,
+        /// Don't ask for a confirmation before deleting the documents
+        #[clap(short, long)]
+        yes: bool,
     },
     /// Create a dump
     Dump,
@@ -78,11 +87,36 @@ pub enum Command {
         /// If you want to use the interactive search. It's a beta feature
         #[clap(long)]
         interactive: bool,
+
+        #[clap(flatten)]
+        params: SearchParameters,
     },
+    /// Search the values of a facet and display how many documents contain them.
+    #[clap(aliases = &["fs"])]
+    FacetSearch(FacetSearch),
+    /// Display the facet distribution and stats of the documents.
+    #[clap(aliases = &["facet", "f"])]
+    Facets(Facets),
+    /// List the documents similar to a document, according to an embedder.
+    #[clap(aliases = &["sim"])]
+    Similar(Similar),
+    /// Run multiple searches in one request, optionally merging their results.
+    /// You can pipe the multi-search in the command as a json, or specify the queries with `--query`.
+    #[clap(aliases = &["ms", "multisearch", "federated-search"])]
+    MultiSearch(MultiSearch),
     /// Get or update the settings.
-    /// You can pipe your settings in the command.
+    /// You can pipe your settings in the command, or use a subcommand to work on a single setting.
     #[clap(aliases = &["set", "setting"])]
-    Settings,
+    Settings {
+        /// Send the piped settings without checking them first
+        #[clap(long)]
+        no_validate: bool,
+        #[clap(subcommand)]
+        command: Option<SettingsCommand>,
+    },
+    /// Create the indexes and update their settings to match a manifest.
+    /// Use `--plan` to only print the changes.
+    Apply(ApplyManifest),
     /// Get or update the keys
     #[clap(subcommand, aliases = &["keys", "k"])]
     Key(Key),
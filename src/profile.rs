@@ -0,0 +1,346 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashSet},
+    hash::{Hash, Hasher},
+    path::PathBuf,
+};
+
+use clap::Parser;
+use miette::Result;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    documents::content_type,
+    format::write_json,
+    validate::{csv_delimiter_byte, for_each_document, Format},
+    Meilisearch,
+};
+
+/// Number of bits of the hash used to select a register of the HyperLogLog.
+const HLL_BITS: u32 = 12;
+const HLL_REGISTERS: usize = 1 << HLL_BITS;
+
+/// Past this number of distinct values a string field is not considered as a facet.
+const MAX_FACET_CARDINALITY: u64 = 1000;
+
+#[derive(Debug, Parser)]
+pub struct ProfileDocuments {
+    /// Only print the suggested settings. They can be piped directly in `mieli settings`.
+    #[clap(long)]
+    settings: bool,
+    /// Only profile the documents of the index matching this filter
+    #[clap(long, aliases = &["filters"], conflicts_with = "file")]
+    filter: Option<String>,
+    /// Set the content-type of your file. It should be either `application/json`, `application/x-ndjson`, `text/csv`.
+    #[clap(short)]
+    content_type: Option<String>,
+    /// Configure the character separating CSV fields. Must be a string containing one ASCII character.
+    #[clap(long)]
+    csv_delimiter: Option<String>,
+    /// Profile the documents of a local file instead of the documents of the index
+    file: Option<PathBuf>,
+}
+
+impl Meilisearch {
+    pub fn profile_documents(&self, params: ProfileDocuments) -> Result<()> {
+        let mut profiler = Profiler::default();
+        match params.file {
+            Some(ref file) => {
+                let format = Format::from_content_type(content_type(
+                    params.content_type.as_deref(),
+                    Some(file),
+                ))?;
+                let csv_delimiter = csv_delimiter_byte(params.csv_delimiter.as_deref())?;
+                for_each_document(file, format, csv_delimiter, |document| {
+                    profiler.profile_document(&document);
+                    Ok(())
+                })?;
+            }
+            None => self.for_each_index_document(params.filter, false, |document| {
+                profiler.profile_document(&document);
+                Ok(())
+            })?,
+        }
+
+        let settings = profiler.suggest_settings();
+        if params.settings {
+            write_json(settings)?;
+        } else {
+            write_json(json!({
+                "documents": profiler.documents,
+                "fields": profiler.fields_report(),
+                "suggestedSettings": settings,
+            }))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Profiler {
+    documents: u64,
+    fields: BTreeMap<String, FieldProfile>,
+}
+
+#[derive(Debug, Default)]
+struct FieldProfile {
+    /// Number of documents containing the field.
+    present: u64,
+    /// Types of the values found directly under this path.
+    types: BTreeMap<&'static str, u64>,
+    /// Number of strings, numbers and booleans, including the ones contained in arrays.
+    strings: u64,
+    numbers: u64,
+    booleans: u64,
+    string_length: u64,
+    cardinality: HyperLogLog,
+    min: Option<f64>,
+    max: Option<f64>,
+    array_lengths: Option<ArrayLengths>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ArrayLengths {
+    min: usize,
+    max: usize,
+    sum: u64,
+    count: u64,
+}
+
+impl Profiler {
+    fn profile_document(&mut self, document: &Map<String, Value>) {
+        self.documents += 1;
+        let mut seen = HashSet::new();
+        for (key, value) in document {
+            self.profile_value(key, value, &mut seen);
+        }
+    }
+
+    fn profile_value(&mut self, path: &str, value: &Value, seen: &mut HashSet<String>) {
+        let field = self.fields.entry(path.to_string()).or_default();
+        if seen.insert(path.to_string()) {
+            field.present += 1;
+        }
+        *field.types.entry(type_name(value)).or_default() += 1;
+
+        match value {
+            Value::Array(values) => {
+                field.array_lengths = Some(match field.array_lengths {
+                    None => ArrayLengths {
+                        min: values.len(),
+                        max: values.len(),
+                        sum: values.len() as u64,
+                        count: 1,
+                    },
+                    Some(lengths) => ArrayLengths {
+                        min: lengths.min.min(values.len()),
+                        max: lengths.max.max(values.len()),
+                        sum: lengths.sum + values.len() as u64,
+                        count: lengths.count + 1,
+                    },
+                });
+                for value in values {
+                    self.profile_element(path, value, seen);
+                }
+            }
+            Value::Object(object) => {
+                for (key, value) in object {
+                    self.profile_value(&format!("{path}.{key}"), value, seen);
+                }
+            }
+            scalar => field.profile_scalar(scalar),
+        }
+    }
+
+    /// Like Meilisearch, the objects contained in an array are flattened under the path of the array.
+    fn profile_element(&mut self, path: &str, value: &Value, seen: &mut HashSet<String>) {
+        match value {
+            Value::Array(values) => {
+                for value in values {
+                    self.profile_element(path, value, seen);
+                }
+            }
+            Value::Object(object) => {
+                for (key, value) in object {
+                    self.profile_value(&format!("{path}.{key}"), value, seen);
+                }
+            }
+            scalar => {
+                if let Some(field) = self.fields.get_mut(path) {
+                    field.profile_scalar(scalar);
+                }
+            }
+        }
+    }
+
+    fn fields_report(&self) -> Value {
+        let documents = self.documents.max(1) as f64;
+        let fields: Map<String, Value> = self
+            .fields
+            .iter()
+            .map(|(path, field)| {
+                let nulls = field.types.get("null").copied().unwrap_or_default();
+                let mut report = json!({
+                    "types": field.types,
+                    "missingRate": (self.documents - field.present) as f64 / documents,
+                    "nullRate": nulls as f64 / documents,
+                });
+                if field.strings + field.numbers + field.booleans > 0 {
+                    report["cardinality"] = json!(field.cardinality.estimate());
+                }
+                if let (Some(min), Some(max)) = (field.min, field.max) {
+                    report["min"] = json!(min);
+                    report["max"] = json!(max);
+                }
+                if let Some(lengths) = field.array_lengths {
+                    report["arrayLength"] = json!({
+                        "min": lengths.min,
+                        "max": lengths.max,
+                        "avg": lengths.sum as f64 / lengths.count as f64,
+                    });
+                }
+                (path.clone(), report)
+            })
+            .collect();
+        Value::Object(fields)
+    }
+
+    fn suggest_settings(&self) -> Value {
+        let mut searchable = Vec::new();
+        let mut filterable = Vec::new();
+        let mut sortable = Vec::new();
+
+        for (path, field) in &self.fields {
+            if path == "_geo" {
+                filterable.push(path.clone());
+                sortable.push(path.clone());
+                continue;
+            }
+            if path.starts_with("_geo.") || path == "_vectors" || path.starts_with("_vectors.") {
+                continue;
+            }
+            let scalars = field.strings + field.numbers + field.booleans;
+            if scalars == 0 {
+                continue;
+            }
+            let dominant = |count: u64| count as f64 >= scalars as f64 * 0.9;
+            let cardinality = field.cardinality.estimate();
+            let distinct_ratio = cardinality as f64 / scalars as f64;
+            // The ids are neither facets nor something users search for.
+            if path.to_lowercase().ends_with("id") && distinct_ratio > 0.9 {
+                continue;
+            }
+
+            if dominant(field.numbers) {
+                filterable.push(path.clone());
+                if field.array_lengths.is_none() {
+                    sortable.push(path.clone());
+                }
+            } else if dominant(field.booleans) {
+                filterable.push(path.clone());
+            } else if dominant(field.strings) {
+                let avg_length = field.string_length as f64 / field.strings as f64;
+                if cardinality <= MAX_FACET_CARDINALITY
+                    && distinct_ratio <= 0.5
+                    && avg_length <= 64.0
+                {
+                    filterable.push(path.clone());
+                }
+                if avg_length >= 20.0 || distinct_ratio >= 0.5 {
+                    searchable.push((avg_length, path.clone()));
+                }
+            }
+        }
+
+        // The shortest fields are usually the titles and should be ranked first.
+        searchable.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        let searchable: Vec<_> = searchable.into_iter().map(|(_, path)| path).collect();
+
+        let mut settings = json!({
+            "filterableAttributes": filterable,
+            "sortableAttributes": sortable,
+        });
+        if !searchable.is_empty() {
+            settings["searchableAttributes"] = json!(searchable);
+        }
+        settings
+    }
+}
+
+impl FieldProfile {
+    fn profile_scalar(&mut self, value: &Value) {
+        let mut hasher = DefaultHasher::new();
+        match value {
+            Value::String(s) => {
+                self.strings += 1;
+                self.string_length += s.chars().count() as u64;
+                s.hash(&mut hasher);
+            }
+            Value::Number(n) => {
+                self.numbers += 1;
+                if let Some(n) = n.as_f64() {
+                    self.min = Some(self.min.map_or(n, |min| min.min(n)));
+                    self.max = Some(self.max.map_or(n, |max| max.max(n)));
+                    n.to_bits().hash(&mut hasher);
+                }
+            }
+            Value::Bool(b) => {
+                self.booleans += 1;
+                b.hash(&mut hasher);
+            }
+            _ => return,
+        }
+        type_name(value).hash(&mut hasher);
+        self.cardinality.insert(hasher.finish());
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Estimate the number of distinct values of a field without storing them.
+#[derive(Debug)]
+struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self {
+            registers: vec![0; HLL_REGISTERS],
+        }
+    }
+}
+
+impl HyperLogLog {
+    fn insert(&mut self, hash: u64) {
+        let register = (hash >> (64 - HLL_BITS)) as usize;
+        let rank = ((hash << HLL_BITS) | (1 << (HLL_BITS - 1))).leading_zeros() as u8 + 1;
+        self.registers[register] = self.registers[register].max(rank);
+    }
+
+    fn estimate(&self) -> u64 {
+        let m = HLL_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&rank| 2_f64.powi(-(rank as i32)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|&&rank| rank == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            // Linear counting is more precise for the small cardinalities
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::fs::File;
+
+use clap::Parser;
+use miette::{bail, Context, IntoDiagnostic, Result};
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{json, Value};
+
+use crate::{
+    copy::restore_embedder_api_keys,
+    documents::{content_type, LocalDocuments},
+    Meilisearch,
+};
+
+#[derive(Debug, Parser)]
+pub struct Reindex {
+    /// The index to reindex.
+    index: String,
+    #[clap(flatten)]
+    documents: LocalDocuments,
+    /// The API key of an embedder, in the format `embedder=key`. Meilisearch redacts the API keys
+    /// of the embedders so they must be provided to embed the documents of the temporary index.
+    #[clap(long)]
+    embedder_api_key: Vec<String>,
+}
+
+impl Meilisearch {
+    /// Send the documents to a temporary index with the same settings and swap it with the live index once it's ready.
+    /// If anything goes wrong the temporary index is deleted and the live index is left untouched.
+    pub fn reindex(&self, params: Reindex) -> Result<()> {
+        let live = Meilisearch {
+            index: params.index,
+            r#async: false,
+            ..self.clone()
+        };
+        let (primary_key, mut settings) = live.fetch_index_definition()?;
+        let missing = restore_embedder_api_keys(&mut settings, &params.embedder_api_key)?;
+        if !missing.is_empty() {
+            bail!(
+                help = format!(
+                    "Use `--embedder-api-key {}=...` for each of them",
+                    missing[0]
+                ),
+                "The API keys of the embedders `{}` are redacted by Meilisearch",
+                missing.join("`, `")
+            );
+        }
+        let primary_key = match params.documents.primary {
+            Some(ref primary_key) => json!(primary_key),
+            None => primary_key,
+        };
+        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+        let tmp = Meilisearch {
+            index: format!("{}-reindex-{now}", live.index),
+            ..live.clone()
+        };
+
+        let ret = tmp
+            .create_index_from(&primary_key, &settings)
+            .and_then(|()| tmp.upload_documents(&params.documents))
+            .and_then(|()| {
+                log::info!("Swapping `{}` and `{}`", live.index, tmp.index);
+                let response = live
+                    .post(format!("{}/swap-indexes", live.addr))
+                    .json(&json!([{ "indexes": [live.index, tmp.index] }]))
+                    .send()
+                    .into_diagnostic()?;
+                live.wait_for_task(response)
+            });
+
+        // After the swap the temporary index contains the old documents. Either way it must be deleted.
+        log::info!("Deleting `{}`", tmp.index);
+        let deletion = tmp
+            .delete(format!("{}/indexes/{}", tmp.addr, tmp.index))
+            .send()
+            .into_diagnostic()
+            .and_then(|response| tmp.wait_for_task(response));
+        match (ret, deletion) {
+            (Err(error), deletion) => {
+                if let Err(e) = deletion {
+                    log::warn!(
+                        "Could not roll back, the index `{}` must be deleted manually: {e}",
+                        tmp.index
+                    );
+                }
+                Err(error).context(format!(
+                    "The reindex failed and has been rolled back. The index `{}` has not been modified",
+                    live.index
+                ))
+            }
+            (Ok(task), Err(e)) => {
+                log::warn!("The old version of the index is still available in `{}` and must be deleted manually: {e}", tmp.index);
+                crate::format::write_json(task)?;
+                Ok(())
+            }
+            (Ok(task), Ok(_)) => {
+                crate::format::write_json(task)?;
+                Ok(())
+            }
+        }
+    }
+
+    fn upload_documents(&self, documents: &LocalDocuments) -> Result<()> {
+        log::info!("Sending `{}` to `{}`", documents.file.display(), self.index);
+        let content_type = content_type(documents.content_type.as_deref(), Some(&documents.file));
+        let mut query = Vec::new();
+        if let Some(ref csv_delimiter) = documents.csv_delimiter {
+            query.push(("csvDelimiter", csv_delimiter));
+        }
+        let file = File::open(&documents.file)
+            .into_diagnostic()
+            .with_context(|| format!("Could not open `{}`", documents.file.display()))?;
+        let response = self
+            .post(format!("{}/indexes/{}/documents", self.addr, self.index))
+            .header(CONTENT_TYPE, content_type)
+            .query(&query)
+            .body(file)
+            .send()
+            .into_diagnostic()?;
+        let task: Value = self.wait_for_task(response)?;
+        log::info!(
+            "{} documents indexed",
+            task["details"]["indexedDocuments"]
+                .as_u64()
+                .unwrap_or_default()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+use std::{io::stdin, path::PathBuf};
+
+use clap::{Parser, ValueEnum};
+use miette::{Context, IntoDiagnostic, Result};
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    format::{write_json, write_table},
+    Meilisearch,
+};
+
+/// The most common search parameters. They're merged with the json piped in the command and take precedence over it.
+#[derive(Debug, Default, Clone, Parser, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchParameters {
+    /// Refine results based on attributes in the `filterableAttributes` list
+    #[clap(long, aliases = &["filters"])]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// Sort the results by an attribute of the `sortableAttributes` list. Eg. `--sort price:asc --sort rating:desc`
+    #[clap(long)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sort: Vec<String>,
+    /// Maximum number of documents returned
+    #[clap(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Number of documents to skip
+    #[clap(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+    /// Request a specific page of results
+    #[clap(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<usize>,
+    /// Maximum number of documents returned per page
+    #[clap(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hits_per_page: Option<usize>,
+    /// Display the count of matches per facet. Eg. `--facets genre,year`
+    #[clap(long, value_delimiter = ',')]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub facets: Vec<String>,
+    /// Attributes to display in the returned documents
+    #[clap(long, value_delimiter = ',')]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes_to_retrieve: Vec<String>,
+    /// Highlight matching terms contained in these attributes
+    #[clap(long, value_delimiter = ',')]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes_to_highlight: Vec<String>,
+    /// Attributes whose values have to be cropped
+    #[clap(long, value_delimiter = ',')]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes_to_crop: Vec<String>,
+    /// Maximum length of cropped value in words
+    #[clap(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crop_length: Option<usize>,
+    /// Strategy used to match query terms within documents
+    #[clap(long, value_enum)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matching_strategy: Option<MatchingStrategy>,
+    /// Display the global ranking score of a document
+    #[clap(long)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub show_ranking_score: bool,
+    /// Display the detailed ranking score of a document
+    #[clap(long)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub show_ranking_score_details: bool,
+    /// Restrict the search to documents with unique values of this attribute
+    #[clap(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distinct: Option<String>,
+    /// Languages used in the query. Eg. `--locales jpn,eng`
+    #[clap(long, value_delimiter = ',')]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub locales: Vec<String>,
+    /// Return the document vector data
+    #[clap(long)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub retrieve_vectors: bool,
+    /// Run an hybrid search with this embedder
+    #[clap(long)]
+    #[serde(skip)]
+    pub embedder: Option<String>,
+    /// Balance between the keyword search (`0.0`) and the semantic search (`1.0`) of an hybrid search
+    #[clap(long, requires = "embedder")]
+    #[serde(skip)]
+    pub semantic_ratio: Option<f64>,
+    /// Search with this vector instead of embedding the query. Can be a json array of floats, or `-` to read it from stdin
+    #[clap(long, conflicts_with = "vector_file")]
+    #[serde(skip)]
+    pub vector: Option<String>,
+    /// Search with the vector contained in this file, as a json array of floats
+    #[clap(long)]
+    #[serde(skip)]
+    pub vector_file: Option<PathBuf>,
+    /// Run the same search with each of these semantic ratios and show how the ranks of the hits change.
+    /// Eg. `--compare-ratios 0,0.5,0.8,1`
+    #[clap(
+        long,
+        value_delimiter = ',',
+        requires = "embedder",
+        conflicts_with_all = &["interactive", "semantic_ratio"]
+    )]
+    #[serde(skip)]
+    pub compare_ratios: Vec<f64>,
+    /// Display every hit with the score of each ranking rule
+    #[clap(long, conflicts_with_all = &["interactive", "compare_ratios"])]
+    #[serde(skip)]
+    pub explain: bool,
+    /// Show the first ranking rule that ranks these two documents differently
+    #[clap(
+        long,
+        num_args = 2,
+        value_names = &["ID_A", "ID_B"],
+        conflicts_with_all = &["interactive", "compare_ratios", "explain"]
+    )]
+    #[serde(skip)]
+    pub why: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchingStrategy {
+    Last,
+    All,
+    Frequency,
+}
+
+impl SearchParameters {
+    /// Build the body of a search request from the json piped in the command, the search terms and the flags.
+    /// When the vector is read from stdin, the json piped in the command is ignored.
+    pub fn into_search_body(self, search: String) -> Result<Map<String, Value>> {
+        let vector_from_stdin = self.vector.as_deref() == Some("-");
+        let mut body = Map::new();
+        if atty::isnt(atty::Stream::Stdin) && !vector_from_stdin {
+            let content = std::io::read_to_string(stdin()).into_diagnostic()?;
+            if !content.trim().is_empty() {
+                body = serde_json::from_str(&content)
+                    .into_diagnostic()
+                    .context("Could not deserialize the piped search as json")?;
+            }
+        }
+        if !search.is_empty() {
+            body.insert("q".to_string(), json!(search));
+        }
+        if let Value::Object(params) = serde_json::to_value(&self).into_diagnostic()? {
+            body.extend(params);
+        }
+
+        if let Some(embedder) = self.embedder {
+            let hybrid = body.entry("hybrid").or_insert_with(|| json!({}));
+            hybrid["embedder"] = json!(embedder);
+            if let Some(ratio) = self.semantic_ratio {
+                hybrid["semanticRatio"] = json!(ratio);
+            }
+        }
+        let vector = match (self.vector, self.vector_file) {
+            (Some(vector), _) if vector == "-" => {
+                Some(parse_vector("stdin", std::io::read_to_string(stdin()))?)
+            }
+            (Some(vector), _) => Some(parse_vector("--vector", Ok(vector))?),
+            (None, Some(path)) => Some(parse_vector(
+                &path.display().to_string(),
+                std::fs::read_to_string(&path),
+            )?),
+            (None, None) => None,
+        };
+        if let Some(vector) = vector {
+            body.insert("vector".to_string(), json!(vector));
+        }
+        Ok(body)
+    }
+}
+
+fn parse_vector(source: &str, content: std::io::Result<String>) -> Result<Vec<f64>> {
+    let content = content
+        .into_diagnostic()
+        .with_context(|| format!("Could not read the vector from `{source}`"))?;
+    serde_json::from_str(&content)
+        .into_diagnostic()
+        .with_context(|| format!("The vector from `{source}` must be a json array of floats"))
+}
+
+impl Meilisearch {
+    /// Run the same search with different semantic ratios and display the ranks of the hits side by side.
+    /// Each hit is followed by how much it moved compared to the previous ratio.
+    pub fn compare_semantic_ratios(
+        &self,
+        mut body: Map<String, Value>,
+        ratios: &[f64],
+    ) -> Result<()> {
+        let primary_key = self.fetch_existing_primary_key()?;
+
+        let mut rankings: Vec<Vec<String>> = Vec::new();
+        for ratio in ratios {
+            body.entry("hybrid").or_insert_with(|| json!({}))["semanticRatio"] = json!(ratio);
+            let response = self
+                .post(format!("{}/indexes/{}/search", self.addr, self.index))
+                .header(CONTENT_TYPE, "application/json")
+                .json(&body)
+                .send()
+                .into_diagnostic()?;
+            let response = self
+                .json_response(response)
+                .with_context(|| format!("Search with the semantic ratio {ratio} failed"))?;
+            let ids = response["hits"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|hit| match &hit[&primary_key] {
+                    Value::String(id) => id.clone(),
+                    id => id.to_string(),
+                })
+                .collect();
+            rankings.push(ids);
+        }
+
+        if atty::isnt(atty::Stream::Stdout) {
+            let comparison: Vec<Value> = ratios
+                .iter()
+                .zip(&rankings)
+                .map(|(ratio, ids)| json!({ "semanticRatio": ratio, "ids": ids }))
+                .collect();
+            write_json(Value::Array(comparison))?;
+            return Ok(());
+        }
+
+        let header: Vec<String> = std::iter::once("rank".to_string())
+            .chain(ratios.iter().map(|ratio| format!("ratio {ratio}")))
+            .collect();
+        let header: Vec<&str> = header.iter().map(String::as_str).collect();
+        let hits = rankings.iter().map(Vec::len).max().unwrap_or_default();
+        let rows: Vec<Vec<String>> = (0..hits)
+            .map(|rank| {
+                let mut row = vec![(rank + 1).to_string()];
+                for (column, ids) in rankings.iter().enumerate() {
+                    let Some(id) = ids.get(rank) else {
+                        row.push(String::new());
+                        continue;
+                    };
+                    let previous = column
+                        .checked_sub(1)
+                        .map(|previous| rankings[previous].iter().position(|other| other == id));
+                    let movement = match previous {
+                        None => String::new(),
+                        Some(None) => " new".to_string(),
+                        Some(Some(previous)) if previous > rank => format!(" ↑{}", previous - rank),
+                        Some(Some(previous)) if previous < rank => format!(" ↓{}", rank - previous),
+                        Some(Some(_)) => " =".to_string(),
+                    };
+                    row.push(format!("{id}{movement}"));
+                }
+                row
+            })
+            .collect();
+        write_table(&header, &rows);
+        Ok(())
+    }
+}
@@ -0,0 +1,331 @@
+use std::io::stdin;
+
+use clap::Parser;
+use miette::{bail, Context, IntoDiagnostic, Result};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    editor,
+    meilisearch::confirm,
+    settings_diff::{diff_values, DiffSettings},
+    settings_import::{ImportStopWords, ImportSynonyms},
+    settings_validate::{validate_settings_file, ValidateSettings},
+    Meilisearch,
+};
+
+#[derive(Debug, Parser)]
+pub enum SettingsCommand {
+    /// Get or update the ranking rules
+    #[clap(subcommand, aliases = &["ranking", "rankingRules", "ranking_rules"])]
+    RankingRules(ListSetting),
+    /// Get or update the searchable attributes
+    #[clap(subcommand, aliases = &["searchable", "searchableAttributes", "searchable_attributes"])]
+    SearchableAttributes(ListSetting),
+    /// Get or update the displayed attributes
+    #[clap(subcommand, aliases = &["displayed", "displayedAttributes", "displayed_attributes"])]
+    DisplayedAttributes(ListSetting),
+    /// Get or update the filterable attributes
+    #[clap(subcommand, aliases = &["filterable", "filterableAttributes", "filterable_attributes"])]
+    FilterableAttributes(ListSetting),
+    /// Get or update the sortable attributes
+    #[clap(subcommand, aliases = &["sortable", "sortableAttributes", "sortable_attributes"])]
+    SortableAttributes(ListSetting),
+    /// Get or update the distinct attribute
+    #[clap(subcommand, aliases = &["distinct", "distinctAttribute", "distinct_attribute"])]
+    DistinctAttribute(ValueSetting),
+    /// Get, update or import the synonyms. Eg. `{ "phone": ["iphone", "android"] }`
+    #[clap(subcommand)]
+    Synonyms(SynonymsSetting),
+    /// Get, update or import the stop words
+    #[clap(subcommand, aliases = &["stopWords", "stop_words"])]
+    StopWords(StopWordsSetting),
+    /// Get or update the typo tolerance
+    #[clap(subcommand, aliases = &["typo", "typoTolerance", "typo_tolerance"])]
+    TypoTolerance(ValueSetting),
+    /// Get or update the pagination
+    #[clap(subcommand)]
+    Pagination(ValueSetting),
+    /// Get or update the faceting
+    #[clap(subcommand)]
+    Faceting(ValueSetting),
+    /// Get or update the proximity precision, either `byWord` or `byAttribute`
+    #[clap(subcommand, aliases = &["proximity", "proximityPrecision", "proximity_precision"])]
+    ProximityPrecision(ValueSetting),
+    /// Get or update the separator tokens
+    #[clap(subcommand, aliases = &["separators", "separatorTokens", "separator_tokens"])]
+    SeparatorTokens(ListSetting),
+    /// Get or update the non-separator tokens
+    #[clap(subcommand, aliases = &["non-separators", "nonSeparatorTokens", "non_separator_tokens"])]
+    NonSeparatorTokens(ListSetting),
+    /// Get or update the dictionary
+    #[clap(subcommand)]
+    Dictionary(ListSetting),
+    /// Get or update the embedders
+    #[clap(subcommand)]
+    Embedders(ValueSetting),
+    /// Get or update the search cutoff in milliseconds
+    #[clap(subcommand, aliases = &["search-cutoff", "searchCutoffMs", "search_cutoff_ms"])]
+    SearchCutoffMs(ValueSetting),
+    /// Get or update the localized attributes. Eg. `[{ "attributePatterns": ["*_ja"], "locales": ["jpn"] }]`
+    #[clap(subcommand, aliases = &["localized", "localizedAttributes", "localized_attributes"])]
+    LocalizedAttributes(ValueSetting),
+    /// Enable or disable the facet search
+    #[clap(subcommand, aliases = &["facetSearch", "facet_search"])]
+    FacetSearch(ValueSetting),
+    /// Get or update the prefix search, either `indexingTime` or `disabled`
+    #[clap(subcommand, aliases = &["prefixSearch", "prefix_search"])]
+    PrefixSearch(ValueSetting),
+    /// Edit the settings in your `$EDITOR`. Only the settings you changed are sent.
+    /// Removing a setting from the json resets it to its default value.
+    Edit,
+    /// Check the settings contained in a json file without sending them to Meilisearch.
+    Validate(ValidateSettings),
+    /// Compare the settings of two indexes, possibly on different instances, or of a json file.
+    Diff(DiffSettings),
+}
+
+/// The actions available on the settings containing a list of strings.
+#[derive(Debug, Parser)]
+pub enum ListSetting {
+    /// Get the current value
+    Get,
+    /// Replace the whole list. If no values are specified the json list piped in the command is used.
+    Set { values: Vec<String> },
+    /// Append values at the end of the list. The values already in the list are ignored.
+    #[clap(aliases = &["append", "push"])]
+    Add {
+        #[clap(required = true)]
+        values: Vec<String>,
+    },
+    /// Remove values from the list.
+    #[clap(aliases = &["rm", "delete", "del"])]
+    Remove {
+        #[clap(required = true)]
+        values: Vec<String>,
+    },
+    /// Reset the setting to its default value
+    Reset,
+}
+
+/// The actions available on the other settings.
+#[derive(Debug, Parser)]
+pub enum ValueSetting {
+    /// Get the current value
+    Get,
+    /// Update the setting. The value is parsed as json and used as a string if it's not valid json.
+    /// If no value is specified the json piped in the command is used.
+    Set { value: Option<String> },
+    /// Reset the setting to its default value
+    Reset,
+}
+
+#[derive(Debug, Parser)]
+pub enum SynonymsSetting {
+    #[clap(flatten)]
+    Value(ValueSetting),
+    /// Import synonyms from a Solr, CSV or json file
+    Import(ImportSynonyms),
+}
+
+#[derive(Debug, Parser)]
+pub enum StopWordsSetting {
+    #[clap(flatten)]
+    List(ListSetting),
+    /// Import stop words from a file containing one word per line
+    Import(ImportStopWords),
+}
+
+impl SettingsCommand {
+    pub fn execute(self, meili: Meilisearch) -> Result<()> {
+        use SettingsCommand::*;
+
+        match self {
+            RankingRules(action) => meili.list_setting("ranking-rules", action),
+            SearchableAttributes(action) => meili.list_setting("searchable-attributes", action),
+            DisplayedAttributes(action) => meili.list_setting("displayed-attributes", action),
+            FilterableAttributes(action) => meili.list_setting("filterable-attributes", action),
+            SortableAttributes(action) => meili.list_setting("sortable-attributes", action),
+            DistinctAttribute(action) => meili.value_setting("distinct-attribute", false, action),
+            Synonyms(SynonymsSetting::Value(action)) => {
+                meili.value_setting("synonyms", false, action)
+            }
+            Synonyms(SynonymsSetting::Import(params)) => meili.import_synonyms(params),
+            StopWords(StopWordsSetting::List(action)) => meili.list_setting("stop-words", action),
+            StopWords(StopWordsSetting::Import(params)) => meili.import_stop_words(params),
+            TypoTolerance(action) => meili.value_setting("typo-tolerance", true, action),
+            Pagination(action) => meili.value_setting("pagination", true, action),
+            Faceting(action) => meili.value_setting("faceting", true, action),
+            ProximityPrecision(action) => meili.value_setting("proximity-precision", false, action),
+            SeparatorTokens(action) => meili.list_setting("separator-tokens", action),
+            NonSeparatorTokens(action) => meili.list_setting("non-separator-tokens", action),
+            Dictionary(action) => meili.list_setting("dictionary", action),
+            Embedders(action) => meili.value_setting("embedders", true, action),
+            SearchCutoffMs(action) => meili.value_setting("search-cutoff-ms", false, action),
+            LocalizedAttributes(action) => {
+                meili.value_setting("localized-attributes", false, action)
+            }
+            FacetSearch(action) => meili.value_setting("facet-search", false, action),
+            PrefixSearch(action) => meili.value_setting("prefix-search", false, action),
+            Edit => meili.edit_settings(),
+            Validate(params) => validate_settings_file(params),
+            Diff(params) => meili.diff_settings(params),
+        }
+    }
+}
+
+impl Meilisearch {
+    fn setting_url(&self, route: &str) -> String {
+        format!("{}/indexes/{}/settings/{}", self.addr, self.index, route)
+    }
+
+    fn list_setting(&self, route: &str, action: ListSetting) -> Result<()> {
+        let values = match action {
+            ListSetting::Get => return self.get_setting(route),
+            ListSetting::Reset => return self.reset_setting(route),
+            ListSetting::Set { values } if values.is_empty() => read_setting_from_stdin()?,
+            ListSetting::Set { values } => json!(values),
+            ListSetting::Add { values } => {
+                let mut list = self.fetch_list_setting(route)?;
+                // `*` means all the attributes and can't be mixed with other attributes.
+                if list == [json!("*")] {
+                    list.clear();
+                }
+                for value in values {
+                    if !list.contains(&json!(value)) {
+                        list.push(json!(value));
+                    }
+                }
+                json!(list)
+            }
+            ListSetting::Remove { values } => {
+                let mut list = self.fetch_list_setting(route)?;
+                list.retain(|value| !values.iter().any(|v| value == v.as_str()));
+                json!(list)
+            }
+        };
+        let response = self
+            .put(self.setting_url(route))
+            .json(&values)
+            .send()
+            .into_diagnostic()?;
+        self.handle_response(response)
+    }
+
+    fn fetch_list_setting(&self, route: &str) -> Result<Vec<Value>> {
+        let response = self.get(self.setting_url(route)).send().into_diagnostic()?;
+        match self.json_response(response)? {
+            Value::Array(list) => Ok(list),
+            Value::Null => Ok(Vec::new()),
+            value => bail!("Expected `{route}` to be a list but got `{value}`"),
+        }
+    }
+
+    /// Some settings must be updated with a `PATCH` and the others with a `PUT`.
+    fn value_setting(&self, route: &str, patch: bool, action: ValueSetting) -> Result<()> {
+        let value = match action {
+            ValueSetting::Get => return self.get_setting(route),
+            ValueSetting::Reset => return self.reset_setting(route),
+            ValueSetting::Set { value: None } => read_setting_from_stdin()?,
+            ValueSetting::Set { value: Some(value) } => {
+                serde_json::from_str(&value).unwrap_or(Value::String(value))
+            }
+        };
+        let request = if patch {
+            self.patch(self.setting_url(route))
+        } else {
+            self.put(self.setting_url(route))
+        };
+        let response = request.json(&value).send().into_diagnostic()?;
+        self.handle_response(response)
+    }
+
+    fn edit_settings(&self) -> Result<()> {
+        let response = self
+            .get(format!("{}/indexes/{}/settings", self.addr, self.index))
+            .send()
+            .into_diagnostic()?;
+        let current = self.json_response(response)?;
+        let Value::Object(edited) = editor::edit_json_until_valid(&current)? else {
+            bail!("The settings must be a json object");
+        };
+        let Value::Object(current) = current else {
+            bail!("Meilisearch returned invalid settings: {current}");
+        };
+
+        // Only the modified settings are sent. The removed settings are reset with `null`.
+        let mut changes: Map<String, Value> = edited
+            .iter()
+            .filter(|(key, value)| current.get(*key) != Some(value))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        for key in current.keys() {
+            if !edited.contains_key(key) {
+                changes.insert(key.clone(), Value::Null);
+            }
+        }
+        if changes.is_empty() {
+            log::info!("The settings were not modified");
+            return Ok(());
+        }
+
+        let before: Map<String, Value> = changes
+            .keys()
+            .map(|key| (key.clone(), current.get(key).cloned().unwrap_or_default()))
+            .collect();
+        let mut differences = Vec::new();
+        diff_values(
+            "",
+            &Value::Object(before),
+            &Value::Object(changes.clone()),
+            &mut differences,
+        );
+        let colored = atty::is(atty::Stream::Stdout);
+        for difference in &differences {
+            difference.print("", colored);
+        }
+        if !confirm("Update the settings?", false)? {
+            return Ok(());
+        }
+
+        // The API keys of the embedders are redacted, sending them back would overwrite the real ones.
+        if let (Some(Value::Object(embedders)), Some(Value::Object(fetched))) =
+            (changes.get_mut("embedders"), current.get("embedders"))
+        {
+            for (name, embedder) in embedders {
+                let fetched = fetched
+                    .get(name)
+                    .and_then(|embedder| embedder.get("apiKey"));
+                if let Some(embedder) = embedder.as_object_mut() {
+                    if fetched.is_some() && embedder.get("apiKey") == fetched {
+                        embedder.remove("apiKey");
+                    }
+                }
+            }
+        }
+        let body = serde_json::to_vec(&changes).into_diagnostic()?;
+        let response = self.update_settings(&self.index, body)?;
+        self.handle_response(response)
+    }
+
+    fn get_setting(&self, route: &str) -> Result<()> {
+        let response = self.get(self.setting_url(route)).send().into_diagnostic()?;
+        self.handle_response(response)
+    }
+
+    fn reset_setting(&self, route: &str) -> Result<()> {
+        let response = self
+            .delete(self.setting_url(route))
+            .send()
+            .into_diagnostic()?;
+        self.handle_response(response)
+    }
+}
+
+fn read_setting_from_stdin() -> Result<Value> {
+    if atty::is(atty::Stream::Stdin) {
+        bail!("You need to specify a value or pipe it in the command as json.");
+    }
+    serde_json::from_reader(stdin())
+        .into_diagnostic()
+        .context("Could not deserialize stdin as json")
+}
@@ -0,0 +1,301 @@
+use std::{collections::HashMap, path::Path};
+
+use clap::Parser;
+use miette::{bail, Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use termion::color;
+
+use crate::Meilisearch;
+
+/// The settings where the order of the list changes the behavior of Meilisearch.
+/// All the other lists are compared as sets.
+const ORDERED_SETTINGS: &[&str] = &[
+    "rankingRules",
+    "searchableAttributes",
+    "localizedAttributes",
+];
+
+/// An instance that can be referred to by its name instead of its address.
+#[derive(Debug, Deserialize)]
+struct Profile {
+    addr: String,
+    key: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct DiffSettings {
+    /// The settings to compare. Can be an index of the current instance, an index of another
+    /// instance in the format `profile:index` or `addr:index`, or a json file. When a file is compared
+    /// with an index, only the settings contained in the file are compared.
+    /// The profiles are read from `$MIELI_PROFILES`, by default `~/.config/mieli/profiles.json`,
+    /// eg. `{ "staging": { "addr": "https://staging.example.com", "key": "..." } }`.
+    a: String,
+    /// The settings to compare against, in the same format.
+    b: String,
+    /// The API key used to fetch `a` when it's on another instance. By default the key provided by `-k` is used.
+    #[clap(long)]
+    key_a: Option<String>,
+    /// The API key used to fetch `b` when it's on another instance. By default the key provided by `-k` is used.
+    #[clap(long)]
+    key_b: Option<String>,
+    /// Exit with the status code 1 if the settings are different. Useful to detect drifts in a CI.
+    #[clap(long)]
+    exit_code: bool,
+}
+
+impl Meilisearch {
+    pub fn diff_settings(&self, params: DiffSettings) -> Result<()> {
+        let mut a = self.load_settings(&params.a, params.key_a)?;
+        let mut b = self.load_settings(&params.b, params.key_b)?;
+        // A file may only contain some settings, the others are not compared.
+        match (
+            Path::new(&params.a).is_file(),
+            Path::new(&params.b).is_file(),
+        ) {
+            (true, false) => only_keep_settings_of(&mut b, &a),
+            (false, true) => only_keep_settings_of(&mut a, &b),
+            _ => (),
+        }
+
+        let mut differences = Vec::new();
+        diff_values("", &a, &b, &mut differences);
+        if differences.is_empty() {
+            log::info!(
+                "The settings of `{}` and `{}` are identical",
+                params.a,
+                params.b
+            );
+            return Ok(());
+        }
+
+        let colored = atty::is(atty::Stream::Stdout);
+        for difference in &differences {
+            difference.print("", colored);
+        }
+        if params.exit_code {
+            std::process::exit(1);
+        }
+        Ok(())
+    }
+
+    fn load_settings(&self, source: &str, key: Option<String>) -> Result<Value> {
+        if Path::new(source).is_file() {
+            let content = std::fs::read_to_string(source)
+                .into_diagnostic()
+                .with_context(|| format!("Could not read `{source}`"))?;
+            return serde_json::from_str(&content)
+                .into_diagnostic()
+                .with_context(|| format!("Could not deserialize `{source}` as json"));
+        }
+
+        let meili = match source.rsplit_once(':') {
+            None => Meilisearch {
+                index: source.to_string(),
+                ..self.clone()
+            },
+            Some((instance, index)) if !instance.is_empty() && !index.is_empty() => {
+                let (addr, profile_key) = match load_profiles()?.remove(instance) {
+                    Some(profile) => (profile.addr, profile.key),
+                    None if instance.contains("://") => (instance.to_string(), None),
+                    None => (format!("http://{instance}"), None),
+                };
+                Meilisearch {
+                    addr,
+                    key: key.or(profile_key).or_else(|| self.key.clone()),
+                    index: index.to_string(),
+                    ..self.clone()
+                }
+            }
+            Some(_) => bail!(
+                help =
+                    "Use `profile:index` or `addr:index` to specify an index on another instance",
+                "`{source}` is neither a file, an index nor an instance"
+            ),
+        };
+        let response = meili
+            .get(format!("{}/indexes/{}/settings", meili.addr, meili.index))
+            .send()
+            .into_diagnostic()?;
+        meili
+            .json_response(response)
+            .with_context(|| format!("Could not retrieve the settings of `{source}`"))
+    }
+}
+
+#[derive(Debug)]
+pub enum Difference {
+    Changed {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+    /// A list where the order matters. Each element is prefixed by `-`, `+` or nothing if it's in both lists.
+    Ordered {
+        path: String,
+        elements: Vec<(char, Value)>,
+    },
+    /// A list compared as a set.
+    Set {
+        path: String,
+        removed: Vec<Value>,
+        added: Vec<Value>,
+    },
+}
+
+impl Difference {
+    pub fn path(&self) -> &str {
+        match self {
+            Difference::Changed { path, .. }
+            | Difference::Ordered { path, .. }
+            | Difference::Set { path, .. } => path,
+        }
+    }
+
+    /// Print the difference with every line prefixed by `indent`.
+    pub fn print(&self, indent: &str, colored: bool) {
+        let line = |sign: char, value: &Value| {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                value => value.to_string(),
+            };
+            match sign {
+                '-' if colored => println!(
+                    "{indent}  {}- {value}{}",
+                    color::Fg(color::Red),
+                    color::Fg(color::Reset)
+                ),
+                '+' if colored => println!(
+                    "{indent}  {}+ {value}{}",
+                    color::Fg(color::Green),
+                    color::Fg(color::Reset)
+                ),
+                sign => println!("{indent}  {sign} {value}"),
+            }
+        };
+        match self {
+            Difference::Changed { path, old, new } => {
+                println!("{indent}{path}");
+                line('-', old);
+                line('+', new);
+            }
+            Difference::Ordered { path, elements } => {
+                println!("{indent}{path} (ordered)");
+                for (sign, value) in elements {
+                    line(*sign, value);
+                }
+            }
+            Difference::Set {
+                path,
+                removed,
+                added,
+            } => {
+                println!("{indent}{path}");
+                removed.iter().for_each(|value| line('-', value));
+                added.iter().for_each(|value| line('+', value));
+            }
+        }
+    }
+}
+
+/// Compare two values recursively and push the differences found.
+pub fn diff_values(path: &str, a: &Value, b: &Value, differences: &mut Vec<Difference>) {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            let keys = a.keys().chain(b.keys().filter(|key| !a.contains_key(*key)));
+            for key in keys {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                let a = a.get(key).unwrap_or(&Value::Null);
+                let b = b.get(key).unwrap_or(&Value::Null);
+                diff_values(&path, a, b, differences);
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if a != b => {
+            let setting = path.split('.').next().unwrap_or_default();
+            if ORDERED_SETTINGS.contains(&setting) {
+                differences.push(Difference::Ordered {
+                    path: path.to_string(),
+                    elements: diff_lists(a, b),
+                });
+            } else {
+                let removed: Vec<Value> = a.iter().filter(|v| !b.contains(v)).cloned().collect();
+                let added: Vec<Value> = b.iter().filter(|v| !a.contains(v)).cloned().collect();
+                if !removed.is_empty() || !added.is_empty() {
+                    differences.push(Difference::Set {
+                        path: path.to_string(),
+                        removed,
+                        added,
+                    });
+                }
+            }
+        }
+        (a, b) if a != b => differences.push(Difference::Changed {
+            path: path.to_string(),
+            old: a.clone(),
+            new: b.clone(),
+        }),
+        _ => (),
+    }
+}
+
+/// Diff two lists with their longest common subsequence.
+fn diff_lists(a: &[Value], b: &[Value]) -> Vec<(char, Value)> {
+    // lcs[i][j] is the length of the longest common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut elements = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            elements.push((' ', a[i].clone()));
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            elements.push(('-', a[i].clone()));
+            i += 1;
+        } else {
+            elements.push(('+', b[j].clone()));
+            j += 1;
+        }
+    }
+    elements
+}
+
+fn only_keep_settings_of(settings: &mut Value, file: &Value) {
+    if let (Value::Object(settings), Value::Object(file)) = (settings, file) {
+        settings.retain(|key, _| file.contains_key(key));
+    }
+}
+
+/// Read the profiles file. It's fine if it doesn't exist, then there is no profile.
+fn load_profiles() -> Result<HashMap<String, Profile>> {
+    let path = match std::env::var("MIELI_PROFILES") {
+        Ok(path) => path,
+        Err(_) => match std::env::var("HOME") {
+            Ok(home) => format!("{home}/.config/mieli/profiles.json"),
+            Err(_) => return Ok(HashMap::new()),
+        },
+    };
+    if !Path::new(&path).is_file() {
+        return Ok(HashMap::new());
+    }
+    let content = std::fs::read_to_string(&path)
+        .into_diagnostic()
+        .with_context(|| format!("Could not read the profiles from `{path}`"))?;
+    serde_json::from_str(&content)
+        .into_diagnostic()
+        .with_context(|| format!("Could not deserialize the profiles of `{path}`"))
+}
@@ -0,0 +1,232 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, ValueEnum};
+use miette::{bail, Context, IntoDiagnostic, Result};
+use serde_json::{json, Map, Value};
+
+use crate::{format::write_json, validate::csv_records, Meilisearch};
+
+#[derive(Debug, Parser)]
+pub struct ImportSynonyms {
+    /// The file containing the synonyms
+    file: PathBuf,
+    /// The format of the file. By default it's inferred from its extension and defaults to `solr`.
+    /// - `solr`: one rule per line, `a, b, c` for multi-way synonyms and `a, b => c` for one-way synonyms.
+    /// - `csv`: one group of multi-way synonyms per line.
+    /// - `json`: either the Meilisearch synonyms object or an array of groups of multi-way synonyms.
+    #[clap(long, value_enum)]
+    format: Option<SynonymsFormat>,
+    /// Replace the current synonyms instead of merging the imported ones with them
+    #[clap(long)]
+    replace: bool,
+    /// Only print the synonyms that would be sent
+    #[clap(long)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SynonymsFormat {
+    Solr,
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Parser)]
+pub struct ImportStopWords {
+    /// The file containing one stop word per line
+    file: PathBuf,
+    /// Replace the current stop words instead of merging the imported ones with them
+    #[clap(long)]
+    replace: bool,
+    /// Only print the stop words that would be sent
+    #[clap(long)]
+    dry_run: bool,
+}
+
+impl Meilisearch {
+    pub fn import_synonyms(&self, params: ImportSynonyms) -> Result<()> {
+        let content = read(&params.file)?;
+        let format = params.format.unwrap_or_else(|| {
+            match params.file.extension().and_then(|ext| ext.to_str()) {
+                Some("json") => SynonymsFormat::Json,
+                Some("csv") => SynonymsFormat::Csv,
+                _ => SynonymsFormat::Solr,
+            }
+        });
+        let mut synonyms = if params.replace {
+            Map::new()
+        } else {
+            match self.fetch_setting("synonyms")? {
+                Value::Object(synonyms) => synonyms,
+                _ => Map::new(),
+            }
+        };
+        match format {
+            SynonymsFormat::Solr => parse_solr_synonyms(&content, &mut synonyms)?,
+            SynonymsFormat::Csv => parse_csv_synonyms(&content, &mut synonyms)?,
+            SynonymsFormat::Json => parse_json_synonyms(&content, &mut synonyms)?,
+        }
+
+        self.send_imported_setting("synonyms", Value::Object(synonyms), params.dry_run)
+    }
+
+    pub fn import_stop_words(&self, params: ImportStopWords) -> Result<()> {
+        let content = read(&params.file)?;
+        let mut stop_words = if params.replace {
+            Vec::new()
+        } else {
+            match self.fetch_setting("stop-words")? {
+                Value::Array(stop_words) => stop_words,
+                _ => Vec::new(),
+            }
+        };
+        for word in content.lines().map(str::trim) {
+            if word.is_empty() || word.starts_with('#') || stop_words.contains(&json!(word)) {
+                continue;
+            }
+            stop_words.push(json!(word));
+        }
+
+        self.send_imported_setting("stopWords", Value::Array(stop_words), params.dry_run)
+    }
+
+    fn fetch_setting(&self, route: &str) -> Result<Value> {
+        let response = self
+            .get(format!(
+                "{}/indexes/{}/settings/{}",
+                self.addr, self.index, route
+            ))
+            .send()
+            .into_diagnostic()?;
+        self.json_response(response)
+    }
+
+    fn send_imported_setting(&self, name: &str, value: Value, dry_run: bool) -> Result<()> {
+        if dry_run {
+            write_json(value)?;
+            return Ok(());
+        }
+        let body = serde_json::to_vec(&json!({ name: value })).into_diagnostic()?;
+        let response = self.update_settings(&self.index, body)?;
+        self.handle_response(response)
+    }
+}
+
+fn read(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path)
+        .into_diagnostic()
+        .with_context(|| format!("Could not read `{}`", path.display()))
+}
+
+/// Every word of the group is a synonym of all the other words of the group.
+fn add_multi_way(synonyms: &mut Map<String, Value>, group: &[String]) {
+    for word in group {
+        let others = group.iter().filter(|other| *other != word);
+        add_one_way(synonyms, word, others);
+    }
+}
+
+fn add_one_way<'a>(
+    synonyms: &mut Map<String, Value>,
+    word: &str,
+    targets: impl IntoIterator<Item = &'a String>,
+) {
+    // A word without any other synonym, like a group of a single word, is skipped.
+    let targets: Vec<&String> = targets
+        .into_iter()
+        .filter(|target| target.as_str() != word)
+        .collect();
+    if targets.is_empty() {
+        return;
+    }
+    let entry = synonyms.entry(word).or_insert_with(|| json!([]));
+    if let Value::Array(values) = entry {
+        for target in targets {
+            if !values.contains(&json!(target)) {
+                values.push(json!(target));
+            }
+        }
+    }
+}
+
+/// Parse the synonyms in the format used by Solr's `SynonymFilter`.
+/// Commas can be escaped with a backslash.
+fn parse_solr_synonyms(content: &str, synonyms: &mut Map<String, Value>) -> Result<()> {
+    let split_terms = |terms: &str| -> Vec<String> {
+        let mut words = Vec::new();
+        let mut word = String::new();
+        let mut chars = terms.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => word.extend(chars.next()),
+                ',' => words.push(std::mem::take(&mut word)),
+                c => word.push(c),
+            }
+        }
+        words.push(word);
+        words
+            .into_iter()
+            .map(|word| word.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|word| !word.is_empty())
+            .collect()
+    };
+
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once("=>") {
+            Some((words, targets)) => {
+                let words = split_terms(words);
+                let targets = split_terms(targets);
+                if words.is_empty() || targets.is_empty() {
+                    bail!("Invalid rule at line {}: `{line}`", line_number + 1);
+                }
+                for word in &words {
+                    add_one_way(synonyms, word, &targets);
+                }
+            }
+            None => add_multi_way(synonyms, &split_terms(line)),
+        }
+    }
+    Ok(())
+}
+
+fn parse_csv_synonyms(content: &str, synonyms: &mut Map<String, Value>) -> Result<()> {
+    for (_, record) in csv_records(content, b',')? {
+        let group: Vec<String> = record
+            .iter()
+            .map(|word| word.trim().to_string())
+            .filter(|word| !word.is_empty())
+            .collect();
+        add_multi_way(synonyms, &group);
+    }
+    Ok(())
+}
+
+fn parse_json_synonyms(content: &str, synonyms: &mut Map<String, Value>) -> Result<()> {
+    let value: Value = serde_json::from_str(content)
+        .into_diagnostic()
+        .context("Could not deserialize the synonyms as json")?;
+    match value {
+        Value::Object(object) => {
+            for (word, targets) in object {
+                let Ok(targets) = serde_json::from_value::<Vec<String>>(targets) else {
+                    bail!("The synonyms of `{word}` must be an array of strings");
+                };
+                add_one_way(synonyms, &word, &targets);
+            }
+        }
+        Value::Array(groups) => {
+            for group in groups {
+                let Ok(group) = serde_json::from_value::<Vec<String>>(group) else {
+                    bail!("Every group of synonyms must be an array of strings");
+                };
+                add_multi_way(synonyms, &group);
+            }
+        }
+        _ => bail!("The synonyms must be an object or an array of arrays of strings"),
+    }
+    Ok(())
+}
@@ -0,0 +1,505 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use miette::{
+    bail, Context, IntoDiagnostic, LabeledSpan, MietteDiagnostic, NamedSource, Result, Severity,
+};
+
+use crate::validate::json_error;
+
+const BUILTIN_RANKING_RULES: &[&str] = &[
+    "words",
+    "typo",
+    "proximity",
+    "attribute",
+    "sort",
+    "exactness",
+    "attributeRank",
+    "wordPosition",
+];
+
+/// The fields accepted by every embedder, except the `userProvided` and `composite` ones.
+const EMBEDDER_COMMON_FIELDS: &[&str] = &[
+    "source",
+    "documentTemplate",
+    "documentTemplateMaxBytes",
+    "dimensions",
+    "distribution",
+    "binaryQuantized",
+];
+
+#[derive(Debug, Parser)]
+pub struct ValidateSettings {
+    /// The json file containing the settings
+    file: PathBuf,
+}
+
+pub fn validate_settings_file(params: ValidateSettings) -> Result<()> {
+    let name = params.file.display().to_string();
+    let content = std::fs::read_to_string(&params.file)
+        .into_diagnostic()
+        .with_context(|| format!("Could not read `{name}`"))?;
+    report_settings_issues(&name, &content, true)?;
+    log::info!("The settings in `{name}` are valid");
+    Ok(())
+}
+
+/// Print the issues found in the settings and return an error if there is any.
+/// Unless `strict` is set, the unknown settings are only warnings since they may have been added
+/// by a more recent version of Meilisearch.
+pub fn report_settings_issues(name: &str, content: &str, strict: bool) -> Result<()> {
+    let issues = check_settings(content);
+    if issues.is_empty() {
+        return Ok(());
+    }
+    let count = issues
+        .iter()
+        .filter(|issue| strict || issue.severity != Some(Severity::Warning))
+        .count();
+    for issue in issues {
+        let report = miette::Report::new(issue)
+            .with_source_code(NamedSource::new(name, content.to_string()));
+        eprintln!("{report:?}");
+    }
+    if count == 0 {
+        return Ok(());
+    }
+    bail!("Found {count} issues in `{name}`")
+}
+
+/// Check the keys, the types and the syntax of the settings without sending them to Meilisearch.
+pub fn check_settings(content: &str) -> Vec<MietteDiagnostic> {
+    if let Err(error) = serde_json::from_str::<serde_json::Value>(content) {
+        return vec![json_error(content, 0, error)];
+    }
+    let settings = SpannedParser::new(content).parse();
+    let mut issues = Vec::new();
+    let Node::Object(fields) = &settings.node else {
+        issues.push(issue(settings.span, "The settings must be a json object"));
+        return issues;
+    };
+
+    for (key, value) in fields {
+        // Every setting can be reset with `null`.
+        if matches!(value.node, Node::Null) {
+            continue;
+        }
+        match key.as_str() {
+            "displayedAttributes"
+            | "searchableAttributes"
+            | "sortableAttributes"
+            | "stopWords"
+            | "separatorTokens"
+            | "nonSeparatorTokens"
+            | "dictionary" => check_strings(value, &mut issues),
+            "filterableAttributes" => match &value.node {
+                Node::Array(values) => values
+                    .iter()
+                    .filter(|value| !matches!(value.node, Node::String(_) | Node::Object(_)))
+                    .for_each(|value| {
+                        issues.push(expected(value, "a string or an attribute pattern object"))
+                    }),
+                _ => issues.push(expected(value, "an array")),
+            },
+            "rankingRules" => check_ranking_rules(value, &mut issues),
+            "distinctAttribute" => check_string(value, &mut issues),
+            "proximityPrecision" => check_enum(value, &["byWord", "byAttribute"], &mut issues),
+            "prefixSearch" => check_enum(value, &["indexingTime", "disabled"], &mut issues),
+            "facetSearch" => check_bool(value, &mut issues),
+            "searchCutoffMs" => check_integer(value, &mut issues),
+            "synonyms" => match &value.node {
+                Node::Object(synonyms) => synonyms
+                    .iter()
+                    .for_each(|(_, v)| check_strings(v, &mut issues)),
+                _ => issues.push(expected(value, "an object")),
+            },
+            "typoTolerance" => check_typo_tolerance(value, &mut issues),
+            "faceting" => check_object(
+                value,
+                &mut issues,
+                &["maxValuesPerFacet", "sortFacetValuesBy"],
+                |key, value, issues| match key {
+                    "maxValuesPerFacet" => check_integer(value, issues),
+                    _ => match &value.node {
+                        Node::Object(fields) => fields
+                            .iter()
+                            .for_each(|(_, v)| check_enum(v, &["alpha", "count"], issues)),
+                        _ => issues.push(expected(value, "an object")),
+                    },
+                },
+            ),
+            "pagination" => {
+                check_object(value, &mut issues, &["maxTotalHits"], |_, value, issues| {
+                    check_integer(value, issues)
+                })
+            }
+            "localizedAttributes" => match &value.node {
+                Node::Array(rules) => rules.iter().for_each(|rule| {
+                    check_object(
+                        rule,
+                        &mut issues,
+                        &["attributePatterns", "locales"],
+                        |_, value, issues| check_strings(value, issues),
+                    )
+                }),
+                _ => issues.push(expected(value, "an array")),
+            },
+            "embedders" => match &value.node {
+                Node::Object(embedders) => embedders
+                    .iter()
+                    .for_each(|(_, embedder)| check_embedder(embedder, &mut issues)),
+                _ => issues.push(expected(value, "an object")),
+            },
+            _ => issues.push(unknown(
+                key.span,
+                &format!("Unknown setting `{}`", key.as_str()),
+            )),
+        }
+    }
+    issues
+}
+
+fn check_ranking_rules(value: &Spanned, issues: &mut Vec<MietteDiagnostic>) {
+    let Node::Array(rules) = &value.node else {
+        issues.push(expected(value, "an array"));
+        return;
+    };
+    for rule in rules {
+        let Node::String(name) = &rule.node else {
+            issues.push(expected(rule, "a string"));
+            continue;
+        };
+        if BUILTIN_RANKING_RULES.contains(&name.as_str()) {
+            continue;
+        }
+        match name.rsplit_once(':') {
+            Some((attribute, "asc" | "desc")) if !attribute.is_empty() => (),
+            Some((attribute, _)) if !attribute.is_empty() => issues.push(
+                MietteDiagnostic::new(format!("Invalid ranking rule `{name}`"))
+                    .with_label(label(rule.span, "the order must be `asc` or `desc`"))
+                    .with_help(format!("Use `{attribute}:asc` or `{attribute}:desc`")),
+            ),
+            _ => issues.push(
+                MietteDiagnostic::new(format!("Invalid ranking rule `{name}`"))
+                    .with_label(label(rule.span, "unknown ranking rule"))
+                    .with_help(format!(
+                        "Use one of {} or `attribute:asc|desc`",
+                        BUILTIN_RANKING_RULES.join(", ")
+                    )),
+            ),
+        }
+    }
+}
+
+fn check_typo_tolerance(value: &Spanned, issues: &mut Vec<MietteDiagnostic>) {
+    let known = [
+        "enabled",
+        "disableOnNumbers",
+        "disableOnWords",
+        "disableOnAttributes",
+        "minWordSizeForTypos",
+    ];
+    check_object(value, issues, &known, |key, value, issues| match key {
+        "enabled" | "disableOnNumbers" => check_bool(value, issues),
+        "disableOnWords" | "disableOnAttributes" => check_strings(value, issues),
+        _ => {
+            check_object(
+                value,
+                issues,
+                &["oneTypo", "twoTypos"],
+                |_, value, issues| match value.node {
+                    Node::Number(n) if n.fract() == 0.0 && (0.0..=255.0).contains(&n) => (),
+                    _ => issues.push(expected(value, "an integer between 0 and 255")),
+                },
+            );
+            if let (Some(one), Some(two)) = (value.get("oneTypo"), value.get("twoTypos")) {
+                if let (Node::Number(a), Node::Number(b)) = (&one.node, &two.node) {
+                    if a > b {
+                        issues.push(
+                            MietteDiagnostic::new(
+                                "`oneTypo` must be lower than or equal to `twoTypos`",
+                            )
+                            .with_labels([
+                                label(one.span, "this threshold"),
+                                label(two.span, "is greater than this one"),
+                            ]),
+                        );
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn check_embedder(embedder: &Spanned, issues: &mut Vec<MietteDiagnostic>) {
+    let Node::Object(fields) = &embedder.node else {
+        issues.push(expected(embedder, "an object"));
+        return;
+    };
+    let Some(source) = embedder.get("source") else {
+        issues.push(issue(embedder.span, "An embedder must have a `source`"));
+        return;
+    };
+    let (specific, required): (&[&str], &[&str]) = match &source.node {
+        Node::String(s) if s == "openAi" => (&["apiKey", "model", "url"], &[]),
+        Node::String(s) if s == "huggingFace" => (&["model", "revision", "pooling"], &[]),
+        Node::String(s) if s == "ollama" => (&["url", "apiKey", "model"], &[]),
+        Node::String(s) if s == "rest" => (
+            &["url", "apiKey", "request", "response", "headers"],
+            &["url", "request", "response"],
+        ),
+        Node::String(s) if s == "userProvided" => (&[], &["dimensions"]),
+        Node::String(s) if s == "composite" => (
+            &["searchEmbedder", "indexingEmbedder"],
+            &["searchEmbedder", "indexingEmbedder"],
+        ),
+        _ => {
+            issues.push(expected(
+                source,
+                "one of `openAi`, `huggingFace`, `ollama`, `rest`, `userProvided` or `composite`",
+            ));
+            return;
+        }
+    };
+    let Node::String(source_name) = &source.node else {
+        return;
+    };
+    let common: &[&str] = match source_name.as_str() {
+        "userProvided" => &["source", "dimensions", "distribution", "binaryQuantized"],
+        "composite" => &["source"],
+        _ => EMBEDDER_COMMON_FIELDS,
+    };
+
+    for (key, value) in fields {
+        if !common.contains(&key.as_str()) && !specific.contains(&key.as_str()) {
+            issues.push(unknown(
+                key.span,
+                &format!(
+                    "The field `{}` is not available for the `{source_name}` embedders",
+                    key.as_str()
+                ),
+            ));
+            continue;
+        }
+        match key.as_str() {
+            "dimensions" | "documentTemplateMaxBytes" => check_integer(value, issues),
+            "binaryQuantized" => check_bool(value, issues),
+            "apiKey" | "model" | "url" | "revision" | "documentTemplate" => {
+                check_string(value, issues)
+            }
+            "searchEmbedder" | "indexingEmbedder" => check_embedder(value, issues),
+            _ => (),
+        }
+    }
+    for field in required {
+        if embedder.get(field).is_none() {
+            issues.push(issue(
+                embedder.span,
+                &format!("The `{source_name}` embedders require the field `{field}`"),
+            ));
+        }
+    }
+}
+
+/// Check every known field of an object with `check` and report the unknown ones.
+fn check_object(
+    value: &Spanned,
+    issues: &mut Vec<MietteDiagnostic>,
+    known: &[&str],
+    mut check: impl FnMut(&str, &Spanned, &mut Vec<MietteDiagnostic>),
+) {
+    let Node::Object(fields) = &value.node else {
+        issues.push(expected(value, "an object"));
+        return;
+    };
+    for (key, value) in fields {
+        if known.contains(&key.as_str()) {
+            check(key.as_str(), value, issues);
+        } else {
+            issues.push(unknown(
+                key.span,
+                &format!("Unknown field `{}`", key.as_str()),
+            ));
+        }
+    }
+}
+
+fn check_strings(value: &Spanned, issues: &mut Vec<MietteDiagnostic>) {
+    match &value.node {
+        Node::Array(values) => values.iter().for_each(|v| check_string(v, issues)),
+        _ => issues.push(expected(value, "an array of strings")),
+    }
+}
+
+fn check_string(value: &Spanned, issues: &mut Vec<MietteDiagnostic>) {
+    if !matches!(value.node, Node::String(_)) {
+        issues.push(expected(value, "a string"));
+    }
+}
+
+fn check_bool(value: &Spanned, issues: &mut Vec<MietteDiagnostic>) {
+    if !matches!(value.node, Node::Bool) {
+        issues.push(expected(value, "a boolean"));
+    }
+}
+
+fn check_integer(value: &Spanned, issues: &mut Vec<MietteDiagnostic>) {
+    match value.node {
+        Node::Number(n) if n.fract() == 0.0 && n >= 0.0 => (),
+        _ => issues.push(expected(value, "a positive integer")),
+    }
+}
+
+fn check_enum(value: &Spanned, variants: &[&str], issues: &mut Vec<MietteDiagnostic>) {
+    match &value.node {
+        Node::String(s) if variants.contains(&s.as_str()) => (),
+        _ => {
+            let variants: Vec<String> = variants.iter().map(|v| format!("`{v}`")).collect();
+            issues.push(expected(value, &format!("one of {}", variants.join(", "))))
+        }
+    }
+}
+
+fn issue(span: (usize, usize), message: &str) -> MietteDiagnostic {
+    MietteDiagnostic::new(message.to_string()).with_label(label(span, "here"))
+}
+
+/// The issues that may come from a more recent version of Meilisearch are warnings.
+fn unknown(span: (usize, usize), message: &str) -> MietteDiagnostic {
+    issue(span, message).with_severity(Severity::Warning)
+}
+
+fn expected(value: &Spanned, expected: &str) -> MietteDiagnostic {
+    MietteDiagnostic::new(format!("Expected {expected}"))
+        .with_label(label(value.span, &format!("expected {expected}")))
+}
+
+fn label((offset, len): (usize, usize), text: &str) -> LabeledSpan {
+    LabeledSpan::new(Some(text.to_string()), offset, len)
+}
+
+/// A json value along with its position in the source.
+#[derive(Debug)]
+struct Spanned {
+    span: (usize, usize),
+    node: Node,
+}
+
+#[derive(Debug)]
+enum Node {
+    Null,
+    Bool,
+    Number(f64),
+    String(String),
+    Array(Vec<Spanned>),
+    Object(Vec<(Spanned, Spanned)>),
+}
+
+impl Spanned {
+    fn as_str(&self) -> &str {
+        match &self.node {
+            Node::String(s) => s,
+            _ => "",
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&Spanned> {
+        match &self.node {
+            Node::Object(fields) => fields
+                .iter()
+                .find(|(k, _)| k.as_str() == key)
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+/// A json parser keeping the span of every value.
+/// It must only be called on a valid json since it doesn't report the syntax errors.
+struct SpannedParser<'a> {
+    content: &'a str,
+    pos: usize,
+}
+
+impl<'a> SpannedParser<'a> {
+    fn new(content: &'a str) -> Self {
+        Self { content, pos: 0 }
+    }
+
+    fn parse(&mut self) -> Spanned {
+        self.skip_whitespace();
+        let start = self.pos;
+        let bytes = self.content.as_bytes();
+        let node = match bytes.get(self.pos) {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    match bytes.get(self.pos) {
+                        Some(b'}') | None => break,
+                        Some(b',') => self.pos += 1,
+                        _ => {
+                            let key = self.parse();
+                            self.skip_whitespace();
+                            self.pos += 1; // the `:`
+                            let value = self.parse();
+                            fields.push((key, value));
+                        }
+                    }
+                }
+                self.pos += 1;
+                Node::Object(fields)
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut values = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    match bytes.get(self.pos) {
+                        Some(b']') | None => break,
+                        Some(b',') => self.pos += 1,
+                        _ => values.push(self.parse()),
+                    }
+                }
+                self.pos += 1;
+                Node::Array(values)
+            }
+            Some(b'"') => {
+                self.pos += 1;
+                while let Some(byte) = bytes.get(self.pos) {
+                    self.pos += if *byte == b'\\' { 2 } else { 1 };
+                    if *byte == b'"' {
+                        break;
+                    }
+                }
+                let raw = &self.content[start..self.pos];
+                Node::String(serde_json::from_str(raw).unwrap_or_default())
+            }
+            _ => {
+                while bytes
+                    .get(self.pos)
+                    .is_some_and(|b| !b",]}".contains(b) && !b.is_ascii_whitespace())
+                {
+                    self.pos += 1;
+                }
+                match &self.content[start..self.pos] {
+                    "null" => Node::Null,
+                    "true" | "false" => Node::Bool,
+                    number => Node::Number(number.parse().unwrap_or_default()),
+                }
+            }
+        };
+        Spanned {
+            span: (start, self.pos - start),
+            node,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let bytes = self.content.as_bytes();
+        while bytes.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use clap::Parser;
+use miette::{bail, IntoDiagnostic, Result};
+use reqwest::{blocking::Response, header::CONTENT_TYPE};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::Meilisearch;
+
+#[derive(Debug, Parser, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Similar {
+    /// The id of the document you want to find similar documents for
+    id: String,
+    /// The embedder used to compare the documents. Can be omitted if the index has only one embedder
+    #[clap(long)]
+    embedder: Option<String>,
+    /// Only return the similar documents matching this filter
+    #[clap(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filter: Option<String>,
+    /// Maximum number of documents returned
+    #[clap(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<usize>,
+    /// Attributes to display in the returned documents
+    #[clap(long, value_delimiter = ',')]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    attributes_to_retrieve: Vec<String>,
+    /// Display the global ranking score of a document
+    #[clap(long)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    show_ranking_score: bool,
+    /// Exclude the documents with a ranking score lower than this value, between `0.0` and `1.0`
+    #[clap(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ranking_score_threshold: Option<f64>,
+}
+
+impl Similar {
+    /// Used by the interactive search to list the similar documents of a hit.
+    pub fn new(id: String, embedder: String) -> Self {
+        Similar {
+            id,
+            embedder: Some(embedder),
+            filter: None,
+            limit: None,
+            attributes_to_retrieve: Vec::new(),
+            show_ranking_score: false,
+            ranking_score_threshold: None,
+        }
+    }
+}
+
+impl Meilisearch {
+    pub fn similar(&self, params: Similar) -> Result<()> {
+        let response = self.similar_request(params)?;
+        self.handle_response(response)
+    }
+
+    pub fn similar_request(&self, mut params: Similar) -> Result<Response> {
+        if params.embedder.is_none() {
+            params.embedder = Some(self.default_embedder()?);
+        }
+        self.post(format!("{}/indexes/{}/similar", self.addr, self.index))
+            .header(CONTENT_TYPE, "application/json")
+            .json(&params)
+            .send()
+            .into_diagnostic()
+    }
+
+    /// Returns the embedder of the index if there is only one.
+    pub fn default_embedder(&self) -> Result<String> {
+        let response = self
+            .get(format!(
+                "{}/indexes/{}/settings/embedders",
+                self.addr, self.index
+            ))
+            .send()
+            .into_diagnostic()?;
+        let embedders = match self.json_response(response)? {
+            Value::Object(embedders) => embedders,
+            _ => Default::default(),
+        };
+        let mut names = embedders.keys();
+        match (names.next(), names.next()) {
+            (Some(name), None) => Ok(name.clone()),
+            (None, _) => bail!("The index `{}` has no embedder", self.index),
+            (Some(_), Some(_)) => bail!(
+                help = format!(
+                    "Specify one of `{}` with `--embedder`",
+                    embedders.keys().cloned().collect::<Vec<_>>().join("`, `")
+                ),
+                "The index `{}` has multiple embedders",
+                self.index
+            ),
+        }
+    }
+}
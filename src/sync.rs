@@ -0,0 +1,266 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    io::Write,
+};
+
+use clap::Parser;
+use miette::{bail, miette, IntoDiagnostic, MietteDiagnostic, Result};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    documents::{AddOrUpdate, LocalDocuments},
+    format::write_json,
+    meilisearch::confirm,
+    validate::{
+        check_document_id, csv_delimiter_byte, for_each_document, get_field, primary_key_candidates,
+    },
+    Meilisearch,
+};
+
+#[derive(Debug, Parser)]
+pub struct SyncDocuments {
+    #[clap(flatten)]
+    pub documents: LocalDocuments,
+    /// Don't ask for a confirmation before deleting the documents missing from the file
+    #[clap(short, long)]
+    pub yes: bool,
+}
+
+#[derive(Debug, Default)]
+struct Changes {
+    added: Vec<String>,
+    changed: Vec<String>,
+    removed: Vec<String>,
+}
+
+impl Changes {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl Meilisearch {
+    pub fn diff_documents(&self, params: LocalDocuments) -> Result<()> {
+        let (_, changes) = self.compute_changes(&params, |_| Ok(()))?;
+        write_json(json!({
+            "added": changes.added,
+            "changed": changes.changed,
+            "removed": changes.removed,
+        }))?;
+        Ok(())
+    }
+
+    pub fn sync_documents(&self, params: SyncDocuments) -> Result<()> {
+        let SyncDocuments {
+            documents: params,
+            yes,
+        } = params;
+        let mut tempfile = tempfile::Builder::new()
+            .suffix(".ndjson")
+            .tempfile()
+            .into_diagnostic()?;
+        let (primary_key, changes) = self.compute_changes(&params, |document| {
+            serde_json::to_writer(&mut tempfile, document).into_diagnostic()?;
+            tempfile.write_all(b"\n").into_diagnostic()
+        })?;
+
+        if changes.is_empty() {
+            log::info!(
+                "The index `{}` is already in sync with `{}`",
+                self.index,
+                params.file.display()
+            );
+            return Ok(());
+        }
+        log::info!(
+            "{} documents added, {} changed and {} removed",
+            changes.added.len(),
+            changes.changed.len(),
+            changes.removed.len()
+        );
+        // Ask before sending anything so a refusal doesn't leave the index half synced.
+        if !changes.removed.is_empty() {
+            let prompt = format!(
+                "Delete the {} documents of the index `{}` missing from `{}`?",
+                changes.removed.len(),
+                self.index,
+                params.file.display()
+            );
+            if !confirm(&prompt, yes)? {
+                return Ok(());
+            }
+        }
+
+        if !changes.added.is_empty() || !changes.changed.is_empty() {
+            tempfile.flush().into_diagnostic()?;
+            let path = tempfile.into_temp_path();
+            self.index_documents(
+                AddOrUpdate {
+                    content_type: Some("application/x-ndjson".to_string()),
+                    primary: primary_key,
+                    file: Some(path.to_path_buf()),
+                    ..AddOrUpdate::default()
+                },
+                false,
+            )?;
+        }
+        if !changes.removed.is_empty() {
+            self.delete_batch(&changes.removed)?;
+        }
+        Ok(())
+    }
+
+    /// Compare the hashes of the documents in the index with the ones in the file.
+    /// `on_change` is called with every document of the file that was added or changed.
+    fn compute_changes(
+        &self,
+        params: &LocalDocuments,
+        mut on_change: impl FnMut(&Map<String, Value>) -> Result<()>,
+    ) -> Result<(Option<String>, Changes)> {
+        let format = params.format()?;
+        let csv_delimiter = csv_delimiter_byte(params.csv_delimiter.as_deref())?;
+        let mut primary_key = match params.primary {
+            Some(ref primary_key) => Some(primary_key.clone()),
+            None => self.fetch_primary_key()?,
+        };
+        // If the index has no primary key yet it must be empty.
+        let mut index = match primary_key {
+            Some(ref primary_key) => self.hash_index_documents(primary_key)?,
+            None => HashMap::new(),
+        };
+
+        let mut changes = Changes::default();
+        for_each_document(&params.file, format, csv_delimiter, |document| {
+            let primary_key = match primary_key {
+                Some(ref primary_key) => primary_key,
+                None => primary_key.insert(infer_primary_key(&document)?),
+            };
+            let id = document_id(&document, primary_key)?;
+            let hash = hash_document(&document);
+            match index.get_mut(&id) {
+                Some((_, seen)) if *seen => log::warn!("Document `{id}` is duplicated in the file"),
+                Some((index_hash, seen)) => {
+                    *seen = true;
+                    if *index_hash != hash {
+                        changes.changed.push(id);
+                        on_change(&document)?;
+                    }
+                }
+                None => {
+                    index.insert(id.clone(), (hash, true));
+                    changes.added.push(id);
+                    on_change(&document)?;
+                }
+            }
+            Ok(())
+        })?;
+
+        changes.removed = index
+            .into_iter()
+            .filter(|(_, (_, seen))| !seen)
+            .map(|(id, _)| id)
+            .collect();
+        changes.added.sort_unstable();
+        changes.changed.sort_unstable();
+        changes.removed.sort_unstable();
+
+        Ok((primary_key, changes))
+    }
+
+    /// Export all the documents of the index and hash them by id.
+    /// The boolean is used to mark the documents that have been seen in the file.
+    fn hash_index_documents(&self, primary_key: &str) -> Result<HashMap<String, (u64, bool)>> {
+        let mut hashes = HashMap::new();
+        let ret = self.for_each_index_document(None, false, |document| {
+            let id = document_id(&document, primary_key)?;
+            hashes.insert(id, (hash_document(&document), false));
+            Ok(())
+        });
+        match ret {
+            Ok(()) => Ok(hashes),
+            // If the index doesn't exist yet it'll be created with the documents of the file
+            Err(error)
+                if error
+                    .downcast_ref::<MietteDiagnostic>()
+                    .and_then(|error| error.code.as_deref())
+                    == Some("index_not_found") =>
+            {
+                Ok(hashes)
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+fn infer_primary_key(document: &Map<String, Value>) -> Result<String> {
+    match primary_key_candidates(document).as_slice() {
+        [primary_key] => {
+            log::info!("Inferred the primary key `{primary_key}`");
+            Ok(primary_key.to_string())
+        }
+        _ => bail!("Could not infer the primary key. Specify it with `--primary`."),
+    }
+}
+
+fn document_id(document: &Map<String, Value>, primary_key: &str) -> Result<String> {
+    let id = get_field(document, primary_key).ok_or_else(|| {
+        miette!(
+            "A document is missing its primary key `{primary_key}`: {}",
+            Value::Object(document.clone())
+        )
+    })?;
+    check_document_id(id).map_err(|e| miette!("{e}. See `mieli documents validate`."))
+}
+
+/// Hash the content of a document regardless of the order of its fields.
+/// The `_vectors` field is ignored since Meilisearch doesn't return it by default.
+fn hash_document(document: &Map<String, Value>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let mut fields: Vec<_> = document
+        .iter()
+        .filter(|(key, _)| *key != "_vectors")
+        .collect();
+    fields.sort_unstable_by_key(|(key, _)| *key);
+    for (key, value) in fields {
+        key.hash(&mut hasher);
+        hash_value(value, &mut hasher);
+    }
+    hasher.finish()
+}
+
+fn hash_value(value: &Value, hasher: &mut impl Hasher) {
+    match value {
+        Value::Null => 0_u8.hash(hasher),
+        Value::Bool(b) => {
+            1_u8.hash(hasher);
+            b.hash(hasher);
+        }
+        Value::Number(n) => {
+            // `1` and `1.0` must be considered equal since the CSV numbers are returned as floats.
+            2_u8.hash(hasher);
+            n.as_f64().map(f64::to_bits).hash(hasher);
+        }
+        Value::String(s) => {
+            3_u8.hash(hasher);
+            s.hash(hasher);
+        }
+        Value::Array(values) => {
+            4_u8.hash(hasher);
+            values.len().hash(hasher);
+            for value in values {
+                hash_value(value, hasher);
+            }
+        }
+        Value::Object(object) => {
+            5_u8.hash(hasher);
+            object.len().hash(hasher);
+            let mut fields: Vec<_> = object.iter().collect();
+            fields.sort_unstable_by_key(|(key, _)| *key);
+            for (key, value) in fields {
+                key.hash(hasher);
+                hash_value(value, hasher);
+            }
+        }
+    }
+}
@@ -0,0 +1,694 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    sync::Arc,
+};
+
+use miette::{
+    bail, Context, IntoDiagnostic, LabeledSpan, MietteDiagnostic, NamedSource, Report, Result,
+};
+use reqwest::StatusCode;
+use serde::{
+    de::{self, value::MapAccessDeserializer, MapAccess, SeqAccess, Visitor},
+    Deserialize,
+};
+use serde_json::{Map, Number, Value};
+
+use crate::Meilisearch;
+
+/// Meilisearch refuses string document ids longer than this.
+const MAX_DOCUMENT_ID_LENGTH: usize = 511;
+/// Past this number of invalid documents we stop printing them one by one.
+const MAX_REPORTED_ISSUES: usize = 50;
+
+/// The formats Meilisearch accept for the documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Ndjson,
+    Csv,
+}
+
+impl Format {
+    pub fn from_content_type(content_type: &str) -> Result<Self> {
+        match content_type {
+            "application/json" => Ok(Format::Json),
+            "application/x-ndjson" => Ok(Format::Ndjson),
+            "text/csv" => Ok(Format::Csv),
+            other => bail!("Cannot validate documents of type `{other}`. Only `application/json`, `application/x-ndjson` and `text/csv` are supported."),
+        }
+    }
+}
+
+/// A document along with its position in the source file.
+#[derive(Debug)]
+pub struct Document {
+    pub span: (usize, usize),
+    pub fields: Map<String, Value>,
+}
+
+impl Meilisearch {
+    /// Parse the documents locally and check they'll be accepted by Meilisearch.
+    /// All the issues are printed on stderr and an error is returned if there was at least one.
+    pub fn validate_documents(
+        &self,
+        name: &str,
+        content: String,
+        format: Format,
+        csv_delimiter: Option<&str>,
+        primary_key: Option<String>,
+    ) -> Result<()> {
+        let delimiter = csv_delimiter_byte(csv_delimiter)?;
+        let source = Arc::new(content);
+        let issues = match parse_documents(&source, format, delimiter) {
+            Ok(documents) => {
+                let primary_key = match primary_key {
+                    Some(primary_key) => Some(primary_key),
+                    None => self.fetch_primary_key().unwrap_or_else(|e| {
+                        log::warn!("Could not retrieve the primary key of the index `{}`, it'll be inferred: {e}", self.index);
+                        None
+                    }),
+                };
+                let issues = check_documents(&documents, primary_key);
+                if issues.is_empty() {
+                    log::info!("{} documents are valid in `{name}`", documents.len());
+                    return Ok(());
+                }
+                issues
+            }
+            Err(issue) => vec![issue],
+        };
+
+        let count = issues.len();
+        for issue in issues.into_iter().take(MAX_REPORTED_ISSUES) {
+            let report = issue.with_source_code(NamedSource::new(name, source.clone()));
+            eprintln!("{report:?}");
+        }
+        if count > MAX_REPORTED_ISSUES {
+            eprintln!("... and {} more", count - MAX_REPORTED_ISSUES);
+        }
+        bail!("Found {count} issues in `{name}`")
+    }
+
+    /// Retrieve the primary key of the index. Returns `None` if the index doesn't exist
+    /// yet or if its primary key has not been set.
+    pub fn fetch_primary_key(&self) -> Result<Option<String>> {
+        let response = self
+            .get(format!("{}/indexes/{}", self.addr, self.index))
+            .send()
+            .into_diagnostic()?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let index = self.json_response(response)?;
+        Ok(index["primaryKey"].as_str().map(String::from))
+    }
+}
+
+pub fn csv_delimiter_byte(csv_delimiter: Option<&str>) -> Result<u8> {
+    match csv_delimiter.map(str::as_bytes) {
+        None => Ok(b','),
+        Some([delimiter]) if delimiter.is_ascii() => Ok(*delimiter),
+        Some(_) => bail!("The CSV delimiter must be a string containing one ASCII character."),
+    }
+}
+
+pub fn parse_documents(content: &str, format: Format, csv_delimiter: u8) -> Result<Vec<Document>> {
+    match format {
+        Format::Json => parse_json(content),
+        Format::Ndjson => parse_ndjson(content),
+        Format::Csv => parse_csv(content, csv_delimiter),
+    }
+}
+
+/// Call `f` on each document of the file without loading all of them in memory.
+/// CSV files are the exception and are entirely parsed first.
+pub fn for_each_document(
+    path: &Path,
+    format: Format,
+    csv_delimiter: u8,
+    mut f: impl FnMut(Map<String, Value>) -> Result<()>,
+) -> Result<()> {
+    let file = File::open(path)
+        .into_diagnostic()
+        .with_context(|| format!("Could not open `{}`", path.display()))?;
+    match format {
+        Format::Csv => {
+            let content = std::io::read_to_string(file).into_diagnostic()?;
+            for document in parse_documents(&content, format, csv_delimiter)? {
+                f(document.fields)?;
+            }
+            Ok(())
+        }
+        Format::Ndjson => {
+            for (line_number, line) in BufReader::new(file).lines().enumerate() {
+                let line = line.into_diagnostic()?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let document = serde_json::from_str(&line)
+                    .into_diagnostic()
+                    .with_context(|| format!("Invalid document at line {}", line_number + 1))?;
+                f(document)?;
+            }
+            Ok(())
+        }
+        Format::Json => {
+            let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(file));
+            let mut visitor = DocumentsVisitor { f, error: None };
+            let ret = de::Deserializer::deserialize_any(&mut deserializer, &mut visitor);
+            if let Some(error) = visitor.error {
+                return Err(error);
+            }
+            ret.into_diagnostic()?;
+            deserializer.end().into_diagnostic()
+        }
+    }
+}
+
+/// Calls its function on a document or each document of an array as they're deserialized.
+struct DocumentsVisitor<F> {
+    f: F,
+    error: Option<Report>,
+}
+
+impl<F: FnMut(Map<String, Value>) -> Result<()>> DocumentsVisitor<F> {
+    fn call<E: de::Error>(&mut self, document: Map<String, Value>) -> Result<(), E> {
+        (self.f)(document).map_err(|error| {
+            self.error = Some(error);
+            E::custom("interrupted")
+        })
+    }
+}
+
+impl<'de, F: FnMut(Map<String, Value>) -> Result<()>> Visitor<'de> for &mut DocumentsVisitor<F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a document or an array of documents")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<(), A::Error> {
+        let document = Map::deserialize(MapAccessDeserializer::new(map))?;
+        self.call(document)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(document) = seq.next_element()? {
+            self.call(document)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run all the checks on the documents. If no primary key is provided it'll be inferred
+/// from the first document, like Meilisearch does.
+pub fn check_documents(documents: &[Document], primary_key: Option<String>) -> Vec<Report> {
+    let mut issues: Vec<MietteDiagnostic> = Vec::new();
+    let primary_key = match primary_key {
+        Some(primary_key) => primary_key,
+        None => match documents.first().map(infer_primary_key) {
+            None => return Vec::new(),
+            Some(Ok(primary_key)) => {
+                log::info!("Inferred the primary key `{primary_key}`");
+                primary_key
+            }
+            Some(Err(issue)) => return vec![issue],
+        },
+    };
+
+    let mut seen: HashMap<String, (usize, usize)> = HashMap::new();
+    for document in documents {
+        match get_field(&document.fields, &primary_key) {
+            None => issues.push(
+                MietteDiagnostic::new(format!("Document is missing its primary key `{primary_key}`"))
+                    .with_label(label(document.span, "this document")),
+            ),
+            Some(id) => match check_document_id(id) {
+                Ok(id) => {
+                    if let Some(first) = seen.get(&id) {
+                        issues.push(
+                            MietteDiagnostic::new(format!("Duplicate document id `{id}`"))
+                                .with_labels([
+                                    label(*first, "first defined here"),
+                                    label(document.span, "defined again here"),
+                                ])
+                                .with_help("Only the last version of the document will be kept by Meilisearch"),
+                        );
+                    } else {
+                        seen.insert(id, document.span);
+                    }
+                }
+                Err(message) => issues.push(
+                    MietteDiagnostic::new(message)
+                        .with_label(label(document.span, "this document"))
+                        .with_help("A document id is an integer or a string of at most 511 bytes containing only alphanumeric characters, hyphens (-) and underscores (_)"),
+                ),
+            },
+        }
+
+        if let Some(geo) = document.fields.get("_geo") {
+            if let Err(message) = check_geo(geo) {
+                issues.push(
+                    MietteDiagnostic::new(message)
+                        .with_label(label(document.span, "this document"))
+                        .with_help(r#"`_geo` must be `null` or an object like `{ "lat": 45.5, "lng": 3.2 }`"#),
+                );
+            }
+        }
+
+        if let Some(vectors) = document.fields.get("_vectors") {
+            if let Err(message) = check_vectors(vectors) {
+                issues.push(
+                    MietteDiagnostic::new(message)
+                        .with_label(label(document.span, "this document"))
+                        .with_help(r#"`_vectors` must be an object mapping each embedder to an array of floats, an array of arrays of floats or an object like `{ "embeddings": [0.1, 0.2], "regenerate": false }`"#),
+                );
+            }
+        }
+    }
+
+    issues.into_iter().map(Report::new).collect()
+}
+
+fn label((offset, len): (usize, usize), text: &str) -> LabeledSpan {
+    LabeledSpan::new(Some(text.to_string()), offset, len)
+}
+
+/// Retrieve a field by its name or by following the dots in its name.
+pub fn get_field<'a>(document: &'a Map<String, Value>, name: &str) -> Option<&'a Value> {
+    if let Some(value) = document.get(name) {
+        return Some(value);
+    }
+    let (head, tail) = name.split_once('.')?;
+    match document.get(head)? {
+        Value::Object(object) => get_field(object, tail),
+        _ => None,
+    }
+}
+
+/// Meilisearch selects the field ending with `id` in the first document.
+pub fn primary_key_candidates(document: &Map<String, Value>) -> Vec<&String> {
+    document
+        .keys()
+        .filter(|key| key.to_lowercase().ends_with("id"))
+        .collect()
+}
+
+fn infer_primary_key(document: &Document) -> Result<String> {
+    let candidates = primary_key_candidates(&document.fields);
+    match candidates.as_slice() {
+        [primary_key] => Ok(primary_key.to_string()),
+        [] => Err(MietteDiagnostic::new("Could not infer the primary key")
+            .with_label(label(
+                document.span,
+                "no field ending with `id` in the first document",
+            ))
+            .with_help("Specify it with `--primary`")
+            .into()),
+        candidates => Err(MietteDiagnostic::new(format!(
+            "Could not infer the primary key, there are multiple candidates: {}",
+            candidates
+                .iter()
+                .map(|candidate| format!("`{candidate}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+        .with_label(label(document.span, "in the first document"))
+        .with_help("Specify it with `--primary`")
+        .into()),
+    }
+}
+
+/// Returns the id as a string if it's valid.
+pub fn check_document_id(id: &Value) -> Result<String, String> {
+    match id {
+        Value::Number(number) if !number.is_f64() => Ok(number.to_string()),
+        Value::String(id) if id.is_empty() => Err("Document id cannot be empty".to_string()),
+        Value::String(id) if id.len() > MAX_DOCUMENT_ID_LENGTH => Err(format!(
+            "Document id `{id}` is {} bytes long, the maximum is {MAX_DOCUMENT_ID_LENGTH}",
+            id.len()
+        )),
+        Value::String(id)
+            if !id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+        {
+            Err(format!("Document id `{id}` contains invalid characters"))
+        }
+        Value::String(id) => Ok(id.clone()),
+        other => Err(format!(
+            "Document id `{other}` must be an integer or a string"
+        )),
+    }
+}
+
+pub fn check_geo(geo: &Value) -> Result<(), String> {
+    let geo = match geo {
+        Value::Null => return Ok(()),
+        Value::Object(geo) => geo,
+        other => return Err(format!("`_geo` must be an object, found `{other}`")),
+    };
+    if let Some(key) = geo.keys().find(|key| *key != "lat" && *key != "lng") {
+        return Err(format!("`_geo` contains the unexpected field `{key}`"));
+    }
+    for (name, max) in [("lat", 90.0), ("lng", 180.0)] {
+        let coordinate = match geo.get(name) {
+            None => return Err(format!("`_geo` is missing the `{name}` field")),
+            Some(Value::Number(n)) => n.as_f64(),
+            Some(Value::String(s)) => s.trim().parse::<f64>().ok(),
+            Some(_) => None,
+        };
+        match coordinate {
+            Some(coordinate) if (-max..=max).contains(&coordinate) => (),
+            Some(coordinate) => {
+                return Err(format!(
+                    "`_geo.{name}` must be between -{max} and {max}, found `{coordinate}`"
+                ))
+            }
+            None => return Err(format!("`_geo.{name}` must be a number")),
+        }
+    }
+    Ok(())
+}
+
+pub fn check_vectors(vectors: &Value) -> Result<(), String> {
+    let vectors = match vectors {
+        Value::Null => return Ok(()),
+        Value::Object(vectors) => vectors,
+        other => return Err(format!("`_vectors` must be an object, found `{other}`")),
+    };
+    for (embedder, value) in vectors {
+        match value {
+            Value::Object(object) => {
+                if let Some(key) = object
+                    .keys()
+                    .find(|key| *key != "embeddings" && *key != "regenerate")
+                {
+                    return Err(format!(
+                        "`_vectors.{embedder}` contains the unexpected field `{key}`"
+                    ));
+                }
+                if let Some(regenerate) = object.get("regenerate") {
+                    if !regenerate.is_boolean() {
+                        return Err(format!("`_vectors.{embedder}.regenerate` must be a boolean"));
+                    }
+                }
+                if let Some(embeddings) = object.get("embeddings") {
+                    if !is_embeddings(embeddings) {
+                        return Err(format!("`_vectors.{embedder}.embeddings` must be an array of floats or an array of arrays of floats"));
+                    }
+                }
+            }
+            value if is_embeddings(value) => (),
+            _ => {
+                return Err(format!(
+                    "`_vectors.{embedder}` must be an array of floats, an array of arrays of floats or an object"
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_embeddings(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(values) if values.iter().all(Value::is_number) => true,
+        Value::Array(values) => values.iter().all(|value| {
+            value
+                .as_array()
+                .is_some_and(|embedding| embedding.iter().all(Value::is_number))
+        }),
+        _ => false,
+    }
+}
+
+/// Convert a line and a column as returned by serde_json to a byte offset in `content`.
+pub fn offset_of(content: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = content
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+    (line_start + column.saturating_sub(1)).min(content.len())
+}
+
+/// Build a diagnostic pointing at the position of a serde_json error.
+pub fn json_error(content: &str, base: usize, error: serde_json::Error) -> MietteDiagnostic {
+    let offset = base + offset_of(&content[base..], error.line(), error.column());
+    MietteDiagnostic::new("Invalid json").with_label(LabeledSpan::new(
+        Some(error.to_string()),
+        offset,
+        0,
+    ))
+}
+
+fn skip_whitespace(content: &str, mut pos: usize) -> usize {
+    let bytes = content.as_bytes();
+    while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+        pos += 1;
+    }
+    pos
+}
+
+fn into_document(value: Value, span: (usize, usize)) -> Result<Document> {
+    match value {
+        Value::Object(fields) => Ok(Document { span, fields }),
+        _ => Err(MietteDiagnostic::new("A document must be a json object")
+            .with_label(label(span, "this is not an object"))
+            .into()),
+    }
+}
+
+/// Parse one json value starting at `pos` and returns it with its end.
+fn parse_json_value(content: &str, pos: usize) -> Result<(Value, usize)> {
+    let mut stream = serde_json::Deserializer::from_str(&content[pos..]).into_iter::<Value>();
+    match stream.next() {
+        Some(Ok(value)) => Ok((value, pos + stream.byte_offset())),
+        Some(Err(error)) => Err(json_error(content, pos, error).into()),
+        None => Err(MietteDiagnostic::new("Unexpected end of file")
+            .with_label(LabeledSpan::new(None, content.len(), 0))
+            .into()),
+    }
+}
+
+/// Parse a json object or an array of objects while keeping track of where each document is.
+fn parse_json(content: &str) -> Result<Vec<Document>> {
+    let mut pos = skip_whitespace(content, 0);
+    if content.as_bytes().get(pos) != Some(&b'[') {
+        let (value, end) = parse_json_value(content, pos)?;
+        let document = into_document(value, (pos, end - pos))?;
+        let trailing = skip_whitespace(content, end);
+        if trailing != content.len() {
+            return Err(
+                MietteDiagnostic::new("Trailing characters after the document")
+                    .with_label(label((trailing, content.len() - trailing), "here"))
+                    .into(),
+            );
+        }
+        return Ok(vec![document]);
+    }
+
+    let mut documents = Vec::new();
+    pos = skip_whitespace(content, pos + 1);
+    if content.as_bytes().get(pos) == Some(&b']') {
+        return Ok(documents);
+    }
+    loop {
+        let (value, end) = parse_json_value(content, pos)?;
+        documents.push(into_document(value, (pos, end - pos))?);
+        pos = skip_whitespace(content, end);
+        match content.as_bytes().get(pos) {
+            Some(b',') => pos = skip_whitespace(content, pos + 1),
+            Some(b']') => break,
+            _ => {
+                return Err(MietteDiagnostic::new("Invalid json")
+                    .with_label(label((pos, 0), "expected `,` or `]`"))
+                    .into())
+            }
+        }
+    }
+    Ok(documents)
+}
+
+fn parse_ndjson(content: &str) -> Result<Vec<Document>> {
+    let mut documents = Vec::new();
+    let mut pos = 0;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if !trimmed.trim_start().is_empty() {
+            let value = serde_json::from_str(trimmed).map_err(|error| {
+                let error_pos = pos + offset_of(trimmed, error.line(), error.column());
+                Report::new(
+                    MietteDiagnostic::new("Invalid json").with_label(LabeledSpan::new(
+                        Some(error.to_string()),
+                        error_pos,
+                        0,
+                    )),
+                )
+            })?;
+            documents.push(into_document(value, (pos, trimmed.len()))?);
+        }
+        pos += line.len();
+    }
+    Ok(documents)
+}
+
+/// The type of a CSV column, specified in the header as `name:type`.
+#[derive(Debug, Clone, Copy)]
+enum CsvType {
+    String,
+    Number,
+    Boolean,
+}
+
+fn parse_csv(content: &str, delimiter: u8) -> Result<Vec<Document>> {
+    let mut records = csv_records(content, delimiter)?.into_iter();
+    let Some((_, header)) = records.next() else {
+        return Ok(Vec::new());
+    };
+    // Like Meilisearch, a suffix that isn't a known type is part of the name of a string column.
+    let columns: Vec<_> = header
+        .into_iter()
+        .map(|column| match column.rsplit_once(':') {
+            Some((name, "string")) => (name.to_string(), CsvType::String),
+            Some((name, "number")) => (name.to_string(), CsvType::Number),
+            Some((name, "boolean")) => (name.to_string(), CsvType::Boolean),
+            _ => (column, CsvType::String),
+        })
+        .collect();
+
+    records
+        .map(|(span, record)| {
+            if record.len() != columns.len() {
+                return Err(MietteDiagnostic::new(format!(
+                    "Expected {} fields but found {}",
+                    columns.len(),
+                    record.len()
+                ))
+                .with_label(label(span, "this line"))
+                .into());
+            }
+            let fields = columns
+                .iter()
+                .zip(record)
+                .map(|((name, ty), field)| {
+                    let value = match ty {
+                        _ if field.is_empty() => Value::Null,
+                        CsvType::String => Value::String(field),
+                        CsvType::Number => parse_csv_number(&field).ok_or_else(|| {
+                            MietteDiagnostic::new(format!(
+                                "Field `{name}` is not a valid number: `{field}`"
+                            ))
+                            .with_label(label(span, "this line"))
+                        })?,
+                        CsvType::Boolean => match field.as_str() {
+                            "true" => Value::Bool(true),
+                            "false" => Value::Bool(false),
+                            _ => {
+                                return Err(MietteDiagnostic::new(format!(
+                                    "Field `{name}` is not a valid boolean: `{field}`"
+                                ))
+                                .with_label(label(span, "this line"))
+                                .into())
+                            }
+                        },
+                    };
+                    Ok((name.clone(), value))
+                })
+                .collect::<Result<_>>()?;
+            Ok(Document { span, fields })
+        })
+        .collect()
+}
+
+fn parse_csv_number(field: &str) -> Option<Value> {
+    let field = field.trim();
+    if let Ok(n) = field.parse::<i64>() {
+        return Some(Value::from(n));
+    }
+    field
+        .parse::<f64>()
+        .ok()
+        .and_then(Number::from_f64)
+        .map(Value::Number)
+}
+
+/// The span of a CSV record along with its fields.
+type CsvRecord = ((usize, usize), Vec<String>);
+
+/// Split the CSV in records while keeping track of their span.
+pub fn csv_records(content: &str, delimiter: u8) -> Result<Vec<CsvRecord>> {
+    let bytes = content.as_bytes();
+    let mut records = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let start = pos;
+        let mut record = Vec::new();
+        let mut field = Vec::new();
+        loop {
+            match bytes.get(pos) {
+                Some(b'"') if field.is_empty() => {
+                    let quote = pos;
+                    pos += 1;
+                    loop {
+                        match bytes.get(pos) {
+                            Some(b'"') if bytes.get(pos + 1) == Some(&b'"') => {
+                                field.push(b'"');
+                                pos += 2;
+                            }
+                            Some(b'"') => {
+                                pos += 1;
+                                break;
+                            }
+                            Some(byte) => {
+                                field.push(*byte);
+                                pos += 1;
+                            }
+                            None => {
+                                return Err(MietteDiagnostic::new("Unterminated quoted field")
+                                    .with_label(label((quote, 1), "this quote is never closed"))
+                                    .into())
+                            }
+                        }
+                    }
+                }
+                Some(byte) if *byte == delimiter => {
+                    record.push(String::from_utf8_lossy(&field).into_owned());
+                    field.clear();
+                    pos += 1;
+                }
+                Some(b'\n') | None => {
+                    let end = pos;
+                    pos += 1;
+                    record.push(String::from_utf8_lossy(&field).into_owned());
+                    let end = if end > start && bytes[end - 1] == b'\r' {
+                        if let Some(last) = record.last_mut() {
+                            if last.ends_with('\r') {
+                                last.pop();
+                            }
+                        }
+                        end - 1
+                    } else {
+                        end
+                    };
+                    if end > start {
+                        records.push(((start, end - start), record));
+                    }
+                    break;
+                }
+                Some(byte) => {
+                    field.push(*byte);
+                    pos += 1;
+                }
+            }
+        }
+    }
+
+    Ok(records)
+}